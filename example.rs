@@ -22,3 +22,32 @@ fn another_one() {
     let x = 12;
     let x = x;
 }
+
+fn modern_syntax(maybe: Option<u32>, items: [u32; 4]) {
+    let Some(value) = maybe else { return };
+    let value = value + 1;
+
+    let [first, rest @ ..] = items;
+    let first = first + rest.len() as u32;
+
+    let future = async move {
+        let value = value * 2;
+        value
+    };
+}
+
+fn const_generic<const N: usize>(input: [u8; N]) {
+    let input = input.len();
+}
+
+async fn fetch(url: &str) -> usize {
+    let url = url.trim();
+    let response = download(url).await;
+
+    let task = async move {
+        let response = response.len();
+        response
+    };
+    let response = task.await;
+    response
+}
@@ -0,0 +1,51 @@
+//! Settings read from a `cargo-light.toml` (or `.cargo-light.toml`) file, so a
+//! project can keep its preferred options next to its source. Command line
+//! flags are combined with the file, a flag can turn a setting on or override
+//! its value.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// The names a config file is looked for under, in order.
+const FILE_NAMES: &[&str] = &["cargo-light.toml", ".cargo-light.toml"];
+
+#[derive(Default, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// Don't count bindings whose name starts with `_`, like `_guard`.
+    pub ignore_underscore: bool,
+    /// Don't report ownership conversions, like `let x = x.clone();`.
+    pub ignore_conversions: bool,
+    /// Same as `--min-depth`.
+    pub min_depth: Option<usize>,
+    /// Same as `--min-distance`.
+    pub min_distance: Option<usize>,
+    /// Same as `--min-density`.
+    pub min_density: Option<f64>,
+    /// Same as `--link-template`.
+    pub link_template: Option<String>,
+    /// The `[theme]` table: a `preset` and the color of each element, see
+    /// the `theme` module.
+    pub theme: BTreeMap<String, String>,
+}
+
+/// Reads the config file at `path`, or the first one found in `dir` when no
+/// path is given. Having no config file isn't an error.
+pub fn load(path: Option<&str>, dir: &str) -> Result<Config, String> {
+    let path = match path {
+        Some(path) => Path::new(path).to_path_buf(),
+        None => match FILE_NAMES
+            .iter()
+            .map(|name| Path::new(dir).join(name))
+            .find(|path| path.is_file())
+        {
+            Some(path) => path,
+            None => return Ok(Config::default()),
+        },
+    };
+
+    let source = fs::read_to_string(&path)
+        .map_err(|e| format!("unable to read {}: {}", path.display(), e))?;
+    toml::from_str(&source).map_err(|e| format!("invalid config {}: {}", path.display(), e))
+}
@@ -0,0 +1,166 @@
+//! Support for analyzing the code blocks in doc comments, which rustdoc
+//! compiles and runs as doctests.
+
+use syn::visit::Visit;
+use syn::{Attribute, Expr, File, Lit, Meta};
+
+/// Rustdoc attributes that a code block can have and still be Rust.
+const RUST_ATTRIBUTES: &[&str] = &[
+    "rust",
+    "ignore",
+    "should_panic",
+    "no_run",
+    "compile_fail",
+    "test_harness",
+    "edition2015",
+    "edition2018",
+    "edition2021",
+    "edition2024",
+];
+
+/// A line of doc comment text, with its line and the column offset of the text.
+type DocLine = (String, usize, usize);
+
+/// The source of a doctest, as rustdoc would compile it.
+pub struct Doctest {
+    pub source: String,
+    /// The doc comment line of the opening fence.
+    pub line: usize,
+    // The doc comment line and column offset of each line of `source`.
+    lines: Vec<(usize, usize)>,
+}
+
+impl Doctest {
+    /// Maps a line and (1-based) column in `source` back to the file with the
+    /// doc comment.
+    pub fn map(&self, line: usize, col: usize) -> (usize, usize) {
+        match self.lines.get(line.wrapping_sub(1)) {
+            Some(&(line, offset)) => (line, col + offset),
+            None => (self.line, col),
+        }
+    }
+}
+
+/// Extracts the Rust code blocks of every doc comment in `file`.
+pub fn extract(file: &File) -> Vec<Doctest> {
+    let mut docs = DocLines::default();
+    docs.visit_file(file);
+
+    let mut doctests = Vec::new();
+    // The open code block, if any: whether it is Rust, the line of its opening
+    // fence and its lines so far.
+    let mut block: Option<(bool, usize, Vec<DocLine>)> = None;
+    let mut prev_line = 0;
+
+    for (text, line, offset) in docs.lines {
+        // A gap between lines starts a separate doc comment, which can't
+        // continue a block left open by the previous one.
+        if line != prev_line + 1 {
+            block = None;
+        }
+        prev_line = line;
+
+        let trimmed = text.trim_start();
+        if let Some(info) = trimmed.strip_prefix("```") {
+            block = match block.take() {
+                Some((is_rust, open, code)) => {
+                    if is_rust {
+                        doctests.push(build(open, code));
+                    }
+                    None
+                }
+                None => Some((is_rust(info), line, Vec::new())),
+            };
+            continue;
+        }
+
+        if let Some((_, _, code)) = &mut block {
+            code.push((hide_marker(&text), line, offset));
+        }
+    }
+
+    doctests
+}
+
+/// Joins the lines of a code block, wrapped in a `main` function unless it
+/// has its own, like rustdoc does.
+fn build(open: usize, code: Vec<DocLine>) -> Doctest {
+    let has_main = code.iter().any(|(text, _, _)| text.contains("fn main"));
+
+    let mut source = String::new();
+    let mut lines = Vec::new();
+    if !has_main {
+        source += "fn main() {\n";
+        lines.push((open, 0));
+    }
+    for (text, line, offset) in code {
+        source += &text;
+        source += "\n";
+        lines.push((line, offset));
+    }
+    if !has_main {
+        source += "}\n";
+    }
+
+    Doctest {
+        source,
+        line: open,
+        lines,
+    }
+}
+
+/// Whether a code block's info string, like `rust,no_run`, makes it a doctest.
+fn is_rust(info: &str) -> bool {
+    info.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|attr| !attr.is_empty())
+        .all(|attr| RUST_ATTRIBUTES.contains(&attr))
+}
+
+/// Lines starting with `# ` are compiled but hidden from the docs. The `#` is
+/// replaced by a space so columns stay the same.
+fn hide_marker(text: &str) -> String {
+    let indent = text.len() - text.trim_start().len();
+    let (start, rest) = text.split_at(indent);
+    if rest == "#" {
+        String::new()
+    } else if rest.starts_with("# ") || rest.starts_with("##") {
+        // `##` escapes a line that really starts with `#`.
+        format!("{} {}", start, &rest[1..])
+    } else {
+        text.into()
+    }
+}
+
+/// Collects every line of doc comment text in a file, in order.
+#[derive(Default)]
+struct DocLines {
+    lines: Vec<DocLine>,
+}
+
+impl<'ast> Visit<'ast> for DocLines {
+    fn visit_attribute(&mut self, i: &'ast Attribute) {
+        if let Some(text) = doc_text(i) {
+            let start = syn::spanned::Spanned::span(i).start();
+            // The text follows the `///` or `//!`. Block comments (`/** */`)
+            // can span several lines, whose offsets are only approximate.
+            // An empty `///` line is still a line.
+            for (n, line) in text.split('\n').enumerate() {
+                self.lines
+                    .push((line.into(), start.line + n, start.column + 3));
+            }
+        }
+    }
+}
+
+fn doc_text(attr: &Attribute) -> Option<String> {
+    match &attr.meta {
+        Meta::NameValue(nv) if nv.path.is_ident("doc") => match &nv.value {
+            Expr::Lit(l) => match &l.lit {
+                Lit::Str(s) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
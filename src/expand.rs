@@ -0,0 +1,187 @@
+//! Support for analyzing macro expanded source, using `cargo expand`
+//! (https://github.com/dtolnay/cargo-expand).
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+/// The macro expanded source of a single target.
+pub struct Expanded {
+    /// A label for the target, like `cargo-light (bin)`.
+    pub name: String,
+    /// The target's root source file.
+    pub src_path: String,
+    pub source: String,
+}
+
+/// Expands every lib, bin, example, test and bench target of the packages in
+/// the workspace at `dir`, or only of the ones named in `packages`. A target
+/// that fails to expand, like one that doesn't compile, gives an error of its
+/// own, the others are still expanded.
+pub fn expand_targets(
+    dir: &str,
+    packages: Option<&[&str]>,
+) -> Result<Vec<Result<Expanded, String>>, String> {
+    let mut expanded = Vec::new();
+    for target in super::workspace::targets(dir)? {
+        if packages.is_some_and(|packages| !packages.contains(&target.package.as_str())) {
+            continue;
+        }
+        // Build scripts can't be expanded on their own.
+        let selection = match target.kind.as_str() {
+            "lib" | "rlib" | "dylib" | "cdylib" | "staticlib" | "proc-macro" => {
+                vec!["--lib".to_string()]
+            }
+            "bin" | "example" | "test" | "bench" => {
+                vec![format!("--{}", target.kind), target.name.clone()]
+            }
+            _ => continue,
+        };
+
+        let output = Command::new("cargo")
+            .arg("expand")
+            .args(["--package", &target.package])
+            .args(&selection)
+            .current_dir(dir)
+            .output()
+            .map_err(|e| format!("unable to run `cargo expand`: {}", e))?;
+
+        if !output.status.success() {
+            expanded.push(Err(format!(
+                "`cargo expand` failed for {} (is cargo-expand installed?): {}",
+                target.name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+            continue;
+        }
+
+        expanded.push(Ok(Expanded {
+            name: format!("{} ({})", target.name, target.kind),
+            src_path: target.src_path.display().to_string(),
+            source: String::from_utf8_lossy(&output.stdout).into_owned(),
+        }));
+    }
+
+    Ok(expanded)
+}
+
+/// Finds where each function of a target is defined in the unexpanded source,
+/// so findings in the expanded code can point back to it. Expansion loses all
+/// span information, so functions are matched by their name qualified with
+/// their module and `impl` type. Expansion keeps the order of a file, so a
+/// name defined more than once in the same file, like a method of two `impl`
+/// blocks for different type parameters, has its locations in the order of
+/// their spans; names defined in several files can't be mapped.
+pub fn original_locations(src_path: &str) -> HashMap<String, Vec<(String, usize, usize)>> {
+    let mut found: HashMap<String, Vec<(String, usize, usize)>> = HashMap::new();
+
+    let root = Path::new(src_path)
+        .parent()
+        .unwrap_or_else(|| Path::new("."));
+    for entry in walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_map(Result::ok)
+    {
+        if !super::is_file_with_ext(&entry, "rs") {
+            continue;
+        }
+
+        let path = entry.path().to_string_lossy().into_owned();
+        let syntax = match std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|source| syn::parse_file(&source).ok())
+        {
+            Some(syntax) => syntax,
+            None => continue,
+        };
+
+        // Expansion inlines the files of modules, so functions are named
+        // after the module of their file too.
+        let mut visitor = FnLocations {
+            path: super::workspace::file_module(root, Path::new(src_path), entry.path()),
+            ..FnLocations::default()
+        };
+        syn::visit::Visit::visit_file(&mut visitor, &syntax);
+
+        for (name, line, col) in visitor.fns {
+            found
+                .entry(name)
+                .or_default()
+                .push((path.clone(), line, col));
+        }
+    }
+
+    found.retain(|_, locs| locs.iter().all(|(path, _, _)| *path == locs[0].0));
+    found
+}
+
+/// The location in the unexpanded source of the `nth` of the `count`
+/// functions named `name` in the expanded source. Functions left out or added
+/// by the expansion, like ones behind a `#[cfg(..)]`, leave a different
+/// number of them, which can't be told apart.
+pub fn original_location(
+    origins: &HashMap<String, Vec<(String, usize, usize)>>,
+    name: &str,
+    nth: usize,
+    count: usize,
+) -> Option<(String, usize, usize)> {
+    let locs = origins.get(name).filter(|locs| locs.len() == count)?;
+    locs.get(nth).cloned()
+}
+
+/// Collects the qualified name, line and (1-based) column of every function
+/// signature.
+#[derive(Default)]
+struct FnLocations {
+    fns: Vec<(String, usize, usize)>,
+    // The modules (or function) being visited, like `ShadowCounter::mod_path`.
+    path: Vec<String>,
+    owner: Option<(String, Option<String>)>,
+}
+
+impl FnLocations {
+    fn visit_fn<F: FnOnce(&mut Self)>(&mut self, sig: &syn::Signature, visit: F) {
+        let name = super::qualified_name(&self.path, self.owner.as_ref(), &sig.ident.to_string());
+        let start = sig.ident.span().start();
+        self.fns.push((name.clone(), start.line, start.column + 1));
+
+        // Nested functions are named after the function they are in.
+        let path = std::mem::replace(&mut self.path, vec![name]);
+        let owner = self.owner.take();
+        visit(self);
+        self.path = path;
+        self.owner = owner;
+    }
+}
+
+impl<'ast> syn::visit::Visit<'ast> for FnLocations {
+    fn visit_item_mod(&mut self, i: &'ast syn::ItemMod) {
+        self.path.push(i.ident.to_string());
+        syn::visit::visit_item_mod(self, i);
+        self.path.pop();
+    }
+
+    fn visit_item_impl(&mut self, i: &'ast syn::ItemImpl) {
+        let owner = std::mem::replace(&mut self.owner, super::impl_owner(i));
+        syn::visit::visit_item_impl(self, i);
+        self.owner = owner;
+    }
+
+    fn visit_item_trait(&mut self, i: &'ast syn::ItemTrait) {
+        let owner = self.owner.replace((i.ident.to_string(), None));
+        syn::visit::visit_item_trait(self, i);
+        self.owner = owner;
+    }
+
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        self.visit_fn(&i.sig, |v| syn::visit::visit_item_fn(v, i));
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.visit_fn(&i.sig, |v| syn::visit::visit_impl_item_fn(v, i));
+    }
+
+    fn visit_trait_item_fn(&mut self, i: &'ast syn::TraitItemFn) {
+        self.visit_fn(&i.sig, |v| syn::visit::visit_trait_item_fn(v, i));
+    }
+}
@@ -0,0 +1,133 @@
+//! Glob patterns, like `src/generated/**` or `*_snapshot.rs`, that files are
+//! left out of the walk by with `--exclude` and `--exclude-path`.
+
+use std::path::Path;
+
+/// Whether the path `relative`, to the directory being walked, is matched by
+/// `pattern`. A pattern with a `/` is matched against the whole path, one
+/// without against each name on it, like in a `.gitignore`: `generated`
+/// leaves out every directory with that name.
+pub fn matches_path(pattern: &str, relative: &Path) -> bool {
+    let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+    let path = relative.to_string_lossy().replace('\\', "/");
+    if pattern.contains('/') {
+        matches(pattern.trim_start_matches('/'), &path)
+    } else {
+        path.split('/').any(|name| matches_name(pattern, name))
+    }
+}
+
+/// Whether `pattern` matches all of the path `text`. A `**` between slashes
+/// matches any number of directories, and the other names of the pattern
+/// one name of the path each, like `matches_name`.
+fn matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let text: Vec<&str> = text.split('/').collect();
+    wildcard(
+        &pattern,
+        &text,
+        |&name| name == "**",
+        |&p, &t| matches_name(p, t),
+    )
+}
+
+/// Whether `pattern` matches all of the name `text`, where `*` matches any
+/// characters and `?` a single one.
+fn matches_name(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    wildcard(&pattern, &text, |&c| c == '*', |&p, &t| p == '?' || p == t)
+}
+
+/// Matches `text` against `pattern`, whose stars match any run of `text`,
+/// and whose other parts match one element each. Only the last star is ever
+/// backtracked to, since an earlier one can't match anything the last one
+/// couldn't, so this takes at most `pattern.len() * text.len()` steps.
+fn wildcard<P, T>(
+    pattern: &[P],
+    text: &[T],
+    is_star: impl Fn(&P) -> bool,
+    matches: impl Fn(&P, &T) -> bool,
+) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // The position of the last star in the pattern, and of the end of the
+    // text it matches.
+    let mut star = None;
+    while t < text.len() {
+        if p < pattern.len() && is_star(&pattern[p]) {
+            star = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && matches(&pattern[p], &text[t]) {
+            p += 1;
+            t += 1;
+        } else if let Some((star_p, star_t)) = star {
+            star = Some((star_p, star_t + 1));
+            p = star_p + 1;
+            t = star_t + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(is_star)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::matches_path;
+    use std::path::Path;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        matches_path(pattern, Path::new(path))
+    }
+
+    #[test]
+    fn names_match_every_name_of_the_path() {
+        assert!(matches("generated", "src/generated/gen.rs"));
+        assert!(matches("generated", "generated"));
+        assert!(matches("*_snapshot.rs", "tests/ui/x_snapshot.rs"));
+        assert!(!matches("generated", "src/generated_code.rs"));
+        assert!(!matches("gen", "src/generated/gen.rs.bak"));
+    }
+
+    #[test]
+    fn paths_match_the_whole_path() {
+        assert!(matches("src/generated", "src/generated"));
+        assert!(matches("./src/generated/", "src/generated"));
+        assert!(matches("/src/*.rs", "src/main.rs"));
+        assert!(!matches("src/generated", "inner/src/generated"));
+        assert!(!matches("src/generated", "src/generated/gen.rs"));
+    }
+
+    #[test]
+    fn stars_stay_within_a_name() {
+        assert!(matches("src/*.rs", "src/main.rs"));
+        assert!(!matches("src/*.rs", "src/output/json.rs"));
+        assert!(matches("src/*/*.rs", "src/output/json.rs"));
+        assert!(matches("src/m*n.rs", "src/main.rs"));
+        assert!(matches("src/*", "src/main.rs"));
+    }
+
+    #[test]
+    fn question_marks_match_one_character_of_a_name() {
+        assert!(matches("src/ma?n.rs", "src/main.rs"));
+        assert!(!matches("src/ma?n.rs", "src/man.rs"));
+        assert!(!matches("src?main.rs", "src/main.rs"));
+        assert!(matches("?.rs", "src/a.rs"));
+    }
+
+    #[test]
+    fn double_stars_match_any_number_of_directories() {
+        assert!(matches("tests/**/*_snapshot.rs", "tests/x_snapshot.rs"));
+        assert!(matches("tests/**/*_snapshot.rs", "tests/a/b/x_snapshot.rs"));
+        assert!(matches("src/generated/**", "src/generated/a/b.rs"));
+        assert!(matches("**/gen.rs", "src/generated/gen.rs"));
+        assert!(!matches("tests/**/*_snapshot.rs", "src/x_snapshot.rs"));
+    }
+
+    #[test]
+    fn many_stars_are_not_backtracked_to_one_by_one() {
+        let name = "a".repeat(10_000);
+        assert!(!matches(&("*a".repeat(50) + "b"), &name));
+        assert!(!matches(&("**/".repeat(50) + "b"), &"a/".repeat(1_000)));
+    }
+}
@@ -0,0 +1,67 @@
+//! Support for source files that are pulled into another one, with
+//! `include!("gen.rs")` or `#[path = "other/file.rs"] mod m;`. Their code
+//! belongs to the including file, so they are analyzed as part of it instead
+//! of on their own.
+
+use syn::{Expr, File, Item, ItemMacro, ItemMod, Lit, LitStr, Meta};
+
+use std::path::{Path, PathBuf};
+
+/// The file that an `include!("..")` item pulls in, if any. `dir` is the
+/// directory of the file the item is in, which relative paths are resolved
+/// from. Paths built by other macros, like `concat!(env!("OUT_DIR"), "/gen.rs")`,
+/// can't be resolved.
+pub fn macro_target(item: &ItemMacro, dir: &Path) -> Option<PathBuf> {
+    if !item.mac.path.is_ident("include") {
+        return None;
+    }
+    let path = item.mac.parse_body::<LitStr>().ok()?;
+    Some(dir.join(path.value()))
+}
+
+/// The file that a `#[path = ".."] mod m;` declaration pulls in, if any.
+/// Inline modules and plain `mod m;` declarations don't pull in anything, the
+/// latter's file is found by walking the directory.
+pub fn mod_target(item: &ItemMod, dir: &Path) -> Option<PathBuf> {
+    if item.content.is_some() {
+        return None;
+    }
+    item.attrs.iter().find_map(|attr| match &attr.meta {
+        Meta::NameValue(nv) if nv.path.is_ident("path") => match &nv.value {
+            Expr::Lit(l) => match &l.lit {
+                Lit::Str(s) => Some(dir.join(s.value())),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// Every file that `file`, at `path`, pulls in at module level (including
+/// inline modules), canonicalized so they can be compared to walked paths.
+pub fn included_files(file: &File, path: &Path) -> Vec<PathBuf> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut found = Vec::new();
+    collect(&file.items, dir, &mut found);
+    found
+}
+
+fn collect(items: &[Item], dir: &Path, found: &mut Vec<PathBuf>) {
+    for item in items {
+        let target = match item {
+            Item::Macro(m) => macro_target(m, dir),
+            Item::Mod(m) => match &m.content {
+                Some((_, items)) => {
+                    collect(items, dir, found);
+                    None
+                }
+                None => mod_target(m, dir),
+            },
+            _ => None,
+        };
+        if let Some(path) = target.and_then(|path| path.canonicalize().ok()) {
+            found.push(path);
+        }
+    }
+}
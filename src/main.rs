@@ -1,44 +1,487 @@
 extern crate clap;
 extern crate colored;
+#[cfg(unix)]
+extern crate libc;
 extern crate proc_macro2;
+#[macro_use]
+extern crate serde;
+extern crate serde_json;
 extern crate syn;
+extern crate toml;
 extern crate walkdir;
 
+mod config;
+mod doctest;
+mod expand;
+mod glob;
+mod include;
+mod output;
+mod theme;
+mod workspace;
+
 use clap::{App, Arg, SubCommand};
 use colored::Colorize;
-use syn::{punctuated::Punctuated, token::Or, visit, Ident, ImplItemMethod, ItemFn, Local, Pat};
+use proc_macro2::{LineColumn, Span, TokenTree};
+use syn::{
+    ext::IdentExt, parse::Parser, punctuated::Punctuated, spanned::Spanned, token::Comma, visit,
+    visit::Visit, Attribute, BinOp, Block, Expr, ExprAsync, ExprAwait, ExprBlock, ExprClosure,
+    ExprConst, ExprForLoop, ExprIf, ExprLet, ExprLoop, ExprMatch, ExprPath, ExprUnsafe, ExprWhile,
+    Fields, File, FnArg, Ident, ImplItemConst, ImplItemFn, Item, ItemConst, ItemEnum, ItemFn,
+    ItemImpl, ItemMacro, ItemMod, ItemStatic, ItemStruct, ItemTrait, Lit, Local, Macro, Meta, Pat,
+    PatIdent, PatType, Signature, Stmt, TraitItemConst, TraitItemFn, Type, UseTree,
+};
+use theme::{Element, Theme};
 use walkdir::{DirEntry, WalkDir};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::env;
 use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+
+/// Names a function after the modules (or function) it is declared in, and
+/// the type and trait of its `impl` block, like `parser::lexer::next_token`,
+/// `Vec3::len` or `<Foo as Display>::fmt`.
+fn qualified_name(path: &[String], owner: Option<&(String, Option<String>)>, name: &str) -> String {
+    let mut path = path.join("::");
+    if !path.is_empty() {
+        path += "::";
+    }
+    match owner {
+        Some((ty, Some(tr))) => format!("<{}{} as {}>::{}", path, ty, tr, name),
+        Some((ty, None)) => format!("{}{}::{}", path, ty, name),
+        None => path + name,
+    }
+}
+
+/// The type of an `impl` block and the trait it implements, if any, which
+/// its methods are named after. Types without a simple name, like `fn()`,
+/// leave their methods unqualified.
+fn impl_owner(i: &ItemImpl) -> Option<(String, Option<String>)> {
+    type_name(&i.self_ty).map(|ty| {
+        let tr = i
+            .trait_
+            .as_ref()
+            .and_then(|(_, path, _)| path.segments.last().map(|s| s.ident.to_string()));
+        (ty, tr)
+    })
+}
+
+/// How a shadowing binding relates to the binding it shadows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShadowClass {
+    /// The new value is computed from the old one, like `let x = x + 1;`.
+    /// This is usually intentional.
+    Derived,
+    /// The new value doesn't use the old one, like `let x = other();`.
+    Unrelated,
+    /// The new value is an owned or borrowed version of the old one, like
+    /// `let x = x.clone();` or `let x = x.as_ref();`.
+    Conversion,
+}
+
+impl std::str::FromStr for ShadowClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "derived" => Ok(ShadowClass::Derived),
+            "unrelated" => Ok(ShadowClass::Unrelated),
+            "conversion" => Ok(ShadowClass::Conversion),
+            _ => Err(format!("unknown shadow classification: {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for ShadowClass {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let class = match self {
+            ShadowClass::Derived => "derived",
+            ShadowClass::Unrelated => "unrelated",
+            ShadowClass::Conversion => "conversion",
+        };
+
+        write!(fmt, "{}", class)
+    }
+}
+
+/// A coarse classification of where a binding's value comes from, so the
+/// history of a variable can be followed at a glance.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InitKind {
+    Literal,
+    Call,
+    /// A method call, on the variable at the root of the receiver if there is
+    /// one, like `x` in `x.trim().len()`.
+    MethodCall(Option<String>),
+    Macro(String),
+    /// Another variable, like `let x = y;`.
+    Variable(String),
+    Reference,
+    Try,
+    Await,
+    Cast,
+    Struct,
+    Closure,
+    /// A block, `if`, `match` or loop.
+    Block,
+    /// Any other expression, like arithmetic.
+    Expression,
+    /// An argument of a function or closure.
+    Argument,
+    /// The variable of a `for` loop.
+    LoopVariable,
+    /// A part of a value matched by a pattern, in `match`, `if let` or
+    /// `let ... else`.
+    Pattern,
+}
+
+impl InitKind {
+    fn of(expr: &Expr) -> Self {
+        match expr {
+            Expr::Lit(_) => InitKind::Literal,
+            Expr::Unary(u) if matches!(&*u.expr, Expr::Lit(_)) => InitKind::Literal,
+            Expr::Call(_) => InitKind::Call,
+            Expr::MethodCall(m) => {
+                let mut receiver = &*m.receiver;
+                while let Expr::MethodCall(m) = receiver {
+                    receiver = &m.receiver;
+                }
+                InitKind::MethodCall(expr_ident(receiver).map(|i| ident_name(&i)))
+            }
+            Expr::Macro(m) => InitKind::Macro(
+                m.mac
+                    .path
+                    .segments
+                    .last()
+                    .map_or(String::new(), |s| s.ident.to_string()),
+            ),
+            Expr::Path(_) => match expr_ident(expr) {
+                Some(ident) => InitKind::Variable(ident_name(&ident)),
+                None => InitKind::Expression,
+            },
+            Expr::Reference(_) => InitKind::Reference,
+            Expr::Try(_) => InitKind::Try,
+            Expr::Await(_) => InitKind::Await,
+            Expr::Cast(_) => InitKind::Cast,
+            Expr::Struct(_) => InitKind::Struct,
+            Expr::Closure(_) => InitKind::Closure,
+            Expr::Block(_)
+            | Expr::If(_)
+            | Expr::Match(_)
+            | Expr::Loop(_)
+            | Expr::Unsafe(_)
+            | Expr::Async(_) => InitKind::Block,
+            Expr::Paren(p) => InitKind::of(&p.expr),
+            Expr::Group(g) => InitKind::of(&g.expr),
+            _ => InitKind::Expression,
+        }
+    }
+}
+
+impl std::fmt::Display for InitKind {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            InitKind::Literal => write!(fmt, "literal"),
+            InitKind::Call => write!(fmt, "function call"),
+            InitKind::MethodCall(Some(receiver)) => write!(fmt, "method call on {}", receiver),
+            InitKind::MethodCall(None) => write!(fmt, "method call"),
+            InitKind::Macro(name) => write!(fmt, "{}!", name),
+            InitKind::Variable(name) => write!(fmt, "from {}", name),
+            InitKind::Reference => write!(fmt, "reference"),
+            InitKind::Try => write!(fmt, "?"),
+            InitKind::Await => write!(fmt, ".await"),
+            InitKind::Cast => write!(fmt, "cast"),
+            InitKind::Struct => write!(fmt, "struct"),
+            InitKind::Closure => write!(fmt, "closure"),
+            InitKind::Block => write!(fmt, "block"),
+            InitKind::Expression => write!(fmt, "expression"),
+            InitKind::Argument => write!(fmt, "argument"),
+            InitKind::LoopVariable => write!(fmt, "loop variable"),
+            InitKind::Pattern => write!(fmt, "pattern"),
+        }
+    }
+}
+
+/// Something about a binding that makes it more (or less) likely to be a
+/// mistake, shown next to its location.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Note {
+    InUnsafe,
+    /// Reuses the name of the binding at this line and column, in a sibling
+    /// scope.
+    ReusesName(usize, usize),
+    /// Changes the type of the variable, from and to, as far as the types
+    /// can be told from the syntax of the two bindings.
+    ChangesApparentType(String, String),
+    AcrossAwait,
+    RebindsScrutinee,
+    MutToImmut,
+    ImmutToMut,
+    ShadowsLoopVariable,
+    ShadowsIteratedCollection,
+    ShadowsClosureCapture,
+    ClosureArgShadowsArg,
+    ClosureArgShadowsLocal,
+    ShadowedValueNeverUsed,
+}
+
+impl Note {
+    /// The notes of `case`, one of the bindings of a variable in `locs`.
+    fn of(case: &Case, locs: &[Case]) -> Vec<Note> {
+        let mut notes = Vec::new();
+        if case.in_unsafe && !case.is_original {
+            notes.push(Note::InUnsafe);
+        }
+        if let Some(reused) = case.reuses.map(|i| &locs[i]) {
+            notes.push(Note::ReusesName(reused.loc, reused.col));
+        }
+
+        let shadowed = match case.shadows.map(|i| &locs[i]) {
+            Some(shadowed) => shadowed,
+            None => return notes,
+        };
+        if case.changes_apparent_type {
+            let from = shadowed.ty.as_deref().unwrap_or("?");
+            let to = case.ty.as_deref().unwrap_or("?");
+            notes.push(Note::ChangesApparentType(from.into(), to.into()));
+        }
+        // A guard, permit or connection that is shadowed while the task was
+        // suspended is easy to misuse.
+        if case.awaits > shadowed.awaits {
+            notes.push(Note::AcrossAwait);
+        }
+        if case.rebinds_scrutinee {
+            notes.push(Note::RebindsScrutinee);
+        }
+        if shadowed.is_mut && !case.is_mut {
+            notes.push(Note::MutToImmut);
+        } else if !shadowed.is_mut && case.is_mut {
+            notes.push(Note::ImmutToMut);
+        }
+        // Shadowing the loop variable in the body hides it for the rest of
+        // every iteration.
+        if shadowed.is_loop_var {
+            notes.push(Note::ShadowsLoopVariable);
+        }
+        if case.shadows_iterable {
+            notes.push(Note::ShadowsIteratedCollection);
+        }
+        // `|req| ..` inside of `fn handle(req: Request)` is a classic mix-up,
+        // so closure arguments say what they shadow.
+        match (case.shadows_capture, case.is_closure_arg) {
+            (false, _) => {}
+            (true, false) => notes.push(Note::ShadowsClosureCapture),
+            (true, true) if shadowed.is_arg => notes.push(Note::ClosureArgShadowsArg),
+            (true, true) => notes.push(Note::ClosureArgShadowsLocal),
+        }
+        // The shadowed value was computed for nothing, which is likely a bug.
+        if !shadowed.is_used {
+            notes.push(Note::ShadowedValueNeverUsed);
+        }
+        notes
+    }
+
+    /// The notes of `case` in a chain that leaves out the allowed bindings,
+    /// like a narrowing `let y = y;`: against the last binding before it that
+    /// is shown, instead of the one it shadows.
+    fn of_shown(case: &Case, locs: &[Case]) -> Vec<Note> {
+        let mut shadows = case.shadows;
+        while let Some(i) = shadows.filter(|&i| locs[i].is_allowed) {
+            shadows = locs[i].shadows;
+        }
+        if shadows == case.shadows {
+            return Note::of(case, locs);
+        }
+        Note::of(
+            &Case {
+                shadows,
+                ..case.clone()
+            },
+            locs,
+        )
+    }
+
+    /// A stable name for the note, for machine readable output.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Note::InUnsafe => "in-unsafe",
+            Note::ReusesName(..) => "reuses-name",
+            Note::ChangesApparentType(..) => "changes-apparent-type",
+            Note::AcrossAwait => "across-await",
+            Note::RebindsScrutinee => "rebinds-scrutinee",
+            Note::MutToImmut => "mut-to-immut",
+            Note::ImmutToMut => "immut-to-mut",
+            Note::ShadowsLoopVariable => "shadows-loop-variable",
+            Note::ShadowsIteratedCollection => "shadows-iterated-collection",
+            Note::ShadowsClosureCapture => "shadows-closure-capture",
+            Note::ClosureArgShadowsArg => "closure-argument-shadows-argument",
+            Note::ClosureArgShadowsLocal => "closure-argument-shadows-local",
+            Note::ShadowedValueNeverUsed => "shadowed-value-never-used",
+        }
+    }
+
+    /// The note in parentheses, colored by how suspicious it is. Changing the
+    /// type stands out more than a change of mutability.
+    fn colored(&self) -> colored::ColoredString {
+        let text = format!("({})", self);
+        match self {
+            Note::InUnsafe | Note::AcrossAwait | Note::RebindsScrutinee => {
+                theme::paint(&text, Element::SevereNote).bold()
+            }
+            Note::MutToImmut | Note::ImmutToMut => text.dimmed(),
+            Note::ShadowedValueNeverUsed | Note::ChangesApparentType(..) => {
+                theme::paint(&text, Element::Unrelated)
+            }
+            _ => theme::paint(&text, Element::Note),
+        }
+    }
+}
+
+impl std::fmt::Display for Note {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Note::InUnsafe => write!(fmt, "in unsafe"),
+            Note::ReusesName(line, col) => write!(fmt, "reuses name from {}:{}", line, col),
+            Note::ChangesApparentType(from, to) => write!(fmt, "apparent type {}→{}", from, to),
+            Note::AcrossAwait => write!(fmt, "across .await"),
+            Note::RebindsScrutinee => write!(fmt, "rebinds match scrutinee"),
+            Note::MutToImmut => write!(fmt, "mut→immut"),
+            Note::ImmutToMut => write!(fmt, "immut→mut"),
+            Note::ShadowsLoopVariable => write!(fmt, "shadows loop variable"),
+            Note::ShadowsIteratedCollection => write!(fmt, "shadows iterated collection"),
+            Note::ShadowsClosureCapture => write!(fmt, "shadows closure capture"),
+            Note::ClosureArgShadowsArg => write!(fmt, "closure argument shadows function argument"),
+            Note::ClosureArgShadowsLocal => write!(fmt, "closure argument shadows local"),
+            Note::ShadowedValueNeverUsed => write!(fmt, "shadowed value never used"),
+        }
+    }
+}
 
 #[derive(Default, Clone, PartialEq, Eq)]
 pub struct Case {
     loc: usize,
-    // TODO: Figure out how to get the matched types.
-    // violates_type: bool,
+    // 1-based, like the columns in compiler messages.
+    col: usize,
+    // Where the statement (or pattern) that makes the binding starts and ends,
+    // so the whole binding can be highlighted and not just its name.
+    stmt: Option<(LineColumn, LineColumn)>,
     is_original: bool,
+    // The binding's type, when it is written out or obvious from the value.
+    ty: Option<String>,
+    // Where the bound value comes from, when it is known.
+    init: Option<InitKind>,
+    // The `let` statement up to the end of its initializer, on one line and
+    // truncated, like `let config = load_from(path)?`.
+    snippet: Option<String>,
+    // The scopes the binding is in, outermost first, like
+    // `fn handler > match arm > closure`.
+    scope: String,
+    // The `#[cfg(..)]` conditions of the items, blocks and statement the
+    // binding is in, outermost first.
+    cfgs: Vec<String>,
+    // How many `.await`s of the function come before the binding.
+    awaits: usize,
+    // A shadow whose type, as far as the syntax tells, differs from the
+    // shadowed binding's.
+    changes_apparent_type: bool,
+    // Index of the shadowed binding in the variable's `locs`.
+    shadows: Option<usize>,
+    // For an original binding that reuses the name of a binding in a sibling
+    // scope (only tracked on request), the index of that binding.
+    reuses: Option<usize>,
+    is_mut: bool,
+    // Bound by the pattern of a `for` loop.
+    is_loop_var: bool,
+    // Whether the bound value is read anywhere.
+    is_used: bool,
+    // A binding inside of a closure that shadows a variable from outside of
+    // it, which the closure can no longer capture.
+    shadows_capture: bool,
+    // An argument of the function.
+    is_arg: bool,
+    // An argument of a closure, like `req` in `|req| ..`.
+    is_closure_arg: bool,
+    // A match arm that just binds the scrutinee again, like the second
+    // `result` in `match result { result => .. }`.
+    rebinds_scrutinee: bool,
+    // A `for` loop variable with the name of the collection it iterates over,
+    // like `for item in item`.
+    shadows_iterable: bool,
+    // Inside of an `unsafe` block, where a shadowed pointer or length is far more
+    // dangerous.
+    in_unsafe: bool,
+    // Only set for shadows.
+    class: Option<ShadowClass>,
+    // A shadow that the options say not to report. It is kept so the binding
+    // stays part of the variable's history.
+    is_allowed: bool,
+    // Found by re-parsing the tokens of a macro invocation, which may not be
+    // how the macro actually expands them.
+    in_macro: bool,
 }
 
 impl std::fmt::Debug for Case {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let loc = self.loc.to_string();
-        let to_write: String;
+        let loc = format!("{}:{}", self.loc, self.col);
+        write!(fmt, "{}", self.paint(&loc))
+    }
+}
 
-        if self.is_original {
-            // .to_string() required for the type annotation on to_write above.
-            to_write = loc.cyan().to_string();
-        } else {
-            to_write = loc.yellow().to_string();
+impl Case {
+    /// `text` in the color of the binding: the one of originals for an
+    /// original, and the one of its classification for a shadow.
+    fn paint(&self, text: &str) -> colored::ColoredString {
+        let element = match self.class {
+            _ if self.is_original => Element::Original,
+            Some(ShadowClass::Unrelated) => Element::Unrelated,
+            Some(ShadowClass::Conversion) => Element::Conversion,
+            _ => Element::Derived,
+        };
+        theme::paint(text, element)
+    }
+
+    fn new(start: LineColumn, is_original: bool) -> Self {
+        Case {
+            loc: start.line,
+            col: start.column + 1,
+            stmt: None,
+            is_original,
+            ty: None,
+            init: None,
+            snippet: None,
+            scope: String::new(),
+            cfgs: Vec::new(),
+            awaits: 0,
+            changes_apparent_type: false,
+            shadows: None,
+            reuses: None,
+            is_mut: false,
+            is_loop_var: false,
+            is_used: false,
+            shadows_capture: false,
+            is_arg: false,
+            is_closure_arg: false,
+            rebinds_scrutinee: false,
+            shadows_iterable: false,
+            in_unsafe: false,
+            class: None,
+            is_allowed: false,
+            in_macro: false,
         }
+    }
 
-        write!(fmt, "{}", to_write)
+    /// Whether this is a shadow (or a reused name) that should be reported.
+    fn is_reported(&self) -> bool {
+        (!self.is_original || self.reuses.is_some()) && !self.is_allowed
     }
-}
 
-impl Case {
-    fn new(loc: usize, is_original: bool) -> Self {
-        Case { loc, is_original }
+    /// How many lines below the end of the statement of `shadowed` the
+    /// binding is.
+    fn distance_from(&self, shadowed: &Case) -> usize {
+        let end = shadowed.stmt.map_or(shadowed.loc, |(_, end)| end.line);
+        self.loc.saturating_sub(end)
     }
 }
 
@@ -47,247 +490,2997 @@ pub struct Count {
     locs: Vec<Case>,
 }
 
-impl Count {
-    fn new() -> Self {
-        Count { locs: Vec::new() }
+/// The kinds of module items that a local binding can be reported for reusing
+/// the name of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ItemKind {
+    Const,
+    Static,
+    // A name brought into scope by a `use` declaration.
+    Import,
+    // An enum variant imported by name or with a glob, like `use Direction::*`.
+    Variant,
+    // A unit struct, like `struct Marker;`.
+    UnitStruct,
+    // A field of the type a method is implemented on, like `self.data`.
+    Field,
+    // A free function, which can't be called below a local with its name.
+    Fn,
+}
+
+impl std::fmt::Display for ItemKind {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let kind = match self {
+            ItemKind::Const => "const",
+            ItemKind::Static => "static",
+            ItemKind::Import => "use",
+            ItemKind::Variant => "variant",
+            ItemKind::UnitStruct => "struct",
+            ItemKind::Field => "field",
+            ItemKind::Fn => "fn",
+        };
+
+        write!(fmt, "{}", kind)
     }
 }
 
+/// A named item declared in a module.
+#[derive(Clone, Debug)]
+pub struct NamedItem {
+    ident: Ident,
+    kind: ItemKind,
+}
+
+/// A local binding that reuses the name of an item in the same module.
+#[derive(Clone, Debug)]
+pub struct ItemShadow {
+    ident: Ident,
+    kind: ItemKind,
+    // The item's declaration, followed by the local binding.
+    locs: Vec<Case>,
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct Function {
     name: String,
+    // What the name is qualified with: the modules the function is declared
+    // in, outermost first, or the function it is nested in.
+    path: Vec<String>,
+    // The modules the function is declared in, outermost first.
+    module: Vec<String>,
+    // For a method, the type of its `impl` block and the trait it implements,
+    // if any. Default methods of a trait have the trait as their type.
+    owner: Option<(String, Option<String>)>,
     loc: usize,
+    col: usize,
+    // The line the function's body ends on.
+    end: usize,
+    // `const` or `static` for the initializer of one, which is analyzed like
+    // a function without arguments.
+    item: Option<&'static str>,
     vars: HashMap<Ident, Count>,
+    items: Vec<ItemShadow>,
+    is_async: bool,
+    // A `#[test]` function, or one inside of a `#[cfg(test)]` module.
+    is_test: bool,
+    // Found in a code block of a doc comment.
+    is_doctest: bool,
+    // Where the function is defined in the unexpanded source, when analyzing
+    // macro expanded code.
+    origin: Option<(String, usize, usize)>,
+    // The file the function is in, when it was pulled into the reported file
+    // with `include!` or `#[path]`.
+    file: Option<String>,
     has_shadow: bool,
 }
 
 impl Function {
-    fn new(name: String, loc: usize) -> Self {
+    fn new(name: String, start: LineColumn, is_async: bool) -> Self {
         Function {
             name,
-            loc,
+            path: Vec::new(),
+            module: Vec::new(),
+            owner: None,
+            loc: start.line,
+            col: start.column + 1,
+            end: start.line,
+            item: None,
             vars: HashMap::new(),
+            items: Vec::new(),
+            is_async,
+            is_test: false,
+            is_doctest: false,
+            origin: None,
+            file: None,
             has_shadow: false,
         }
     }
+
+    /// The function's name, qualified with the modules it is declared in, like
+    /// `parser::lexer::next_token`, and with the type of its `impl` block for
+    /// methods, like `Vec3::len` or `<Foo as Display>::fmt`.
+    fn full_name(&self) -> String {
+        qualified_name(&self.path, self.owner.as_ref(), &self.name)
+    }
+
+    /// The function's variables, by where they are first bound, so reports
+    /// come out the same on every run.
+    fn sorted_vars(&self) -> Vec<(&Ident, &Count)> {
+        let mut vars: Vec<(&Ident, &Count)> = self.vars.iter().collect();
+        vars.sort_by_key(|(ident, count)| {
+            let first = count.locs.first().map(|case| (case.loc, case.col));
+            (first, ident.to_string())
+        });
+        vars
+    }
+
+    /// How many shadows (and reused names) are reported in the function.
+    fn shadows(&self) -> usize {
+        let vars = self.vars.values().flat_map(|count| &count.locs);
+        vars.filter(|case| case.is_reported()).count() + self.items.len()
+    }
+
+    /// How many variables (and items) have a shadow reported in the function,
+    /// however many times each is shadowed.
+    fn shadowed_variables(&self) -> usize {
+        // A name can hide an item several times, and be shadowed as a
+        // variable too, it is still one name.
+        let vars = self.vars.iter();
+        let names: HashSet<&Ident> = vars
+            .filter(|(_, count)| count.locs.iter().any(Case::is_reported))
+            .map(|(ident, _)| ident)
+            .chain(self.items.iter().map(|item| &item.ident))
+            .collect();
+        names.len()
+    }
+
+    /// How many lines the function spans, counting the first and last.
+    fn lines(&self) -> usize {
+        self.end.saturating_sub(self.loc) + 1
+    }
+
+    /// Reported shadows per 100 lines of the function, so long functions
+    /// aren't ranked the same as short ones with as many shadows.
+    fn density(&self) -> f64 {
+        self.shadows() as f64 * 100.0 / self.lines() as f64
+    }
+
+    /// Moves every location in the function with `map`, which takes and
+    /// returns a line and a 1-based column.
+    fn map_locations<F: Fn(usize, usize) -> (usize, usize)>(&mut self, map: F) {
+        let map_case = |case: &mut Case| {
+            let (loc, col) = map(case.loc, case.col);
+            case.loc = loc;
+            case.col = col;
+            if let Some((start, end)) = &mut case.stmt {
+                for pos in [start, end] {
+                    let (line, col) = map(pos.line, pos.column + 1);
+                    *pos = LineColumn {
+                        line,
+                        column: col - 1,
+                    };
+                }
+            }
+        };
+
+        let (loc, col) = map(self.loc, self.col);
+        self.loc = loc;
+        self.col = col;
+        self.end = map(self.end, 1).0;
+        for count in self.vars.values_mut() {
+            count.locs.iter_mut().for_each(map_case);
+        }
+        for item in &mut self.items {
+            item.locs.iter_mut().for_each(map_case);
+        }
+    }
 }
 
 impl std::fmt::Display for Function {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let vars = &self.vars;
-        let head = format!(
-            "  {} {:>3} {:<15}",
-            "line:".bright_magenta(),
-            self.loc.to_string().bright_magenta(),
-            self.name.bright_green()
+        let layout = Layout {
+            max_name_width: 15,
+            width: None,
+            markers: false,
+        };
+        write!(fmt, "{}", self.render(&|_, _, text| text, &[], layout))
+    }
+}
+
+/// Right-aligns the location `line:col` in 7 columns, with the text (but not
+/// the padding) passed through `link`.
+fn aligned_location(
+    line: usize,
+    col: usize,
+    link: &dyn Fn(usize, usize, String) -> String,
+    paint: fn(&str) -> colored::ColoredString,
+) -> String {
+    let loc = format!("{}:{}", line, col);
+    let pad = " ".repeat(7usize.saturating_sub(loc.len()));
+    format!("{}{}", pad, link(line, col, paint(&loc).to_string()))
+}
+
+/// How wide the columns of a function are in the terminal.
+#[derive(Clone, Copy, Debug)]
+pub struct Layout {
+    /// The longest a variable name is shown before it is cut off.
+    pub max_name_width: usize,
+    /// The width of the terminal, which the chains of bindings are wrapped
+    /// at, if there is one.
+    pub width: Option<usize>,
+    /// Mark originals with `*` and shadows with `→`, so they can be told
+    /// apart without colors.
+    pub markers: bool,
+}
+
+/// The width of the terminal that stdout is, if it is one. `COLUMNS` can
+/// override it.
+pub fn terminal_width() -> Option<usize> {
+    if let Some(columns) = env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(columns);
+    }
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    window_columns()
+}
+
+#[cfg(unix)]
+fn window_columns() -> Option<usize> {
+    // The size of the window is only known to the terminal, which is asked
+    // about the one stdout is.
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let found = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+    (found && size.ws_col > 0).then_some(size.ws_col as usize)
+}
+
+#[cfg(not(unix))]
+fn window_columns() -> Option<usize> {
+    None
+}
+
+/// `name` cut off with `…` when it is longer than `width` characters.
+fn fit(name: &str, width: usize) -> String {
+    if name.chars().count() <= width {
+        return name.into();
+    }
+    let cut: String = name.chars().take(width.saturating_sub(1)).collect();
+    cut + "…"
+}
+
+/// How many columns `text` takes up in the terminal, without its escape codes.
+fn visible_len(text: &str) -> usize {
+    let mut len = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Color codes end with a letter, like `\x1b[1;31m`.
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            len += 1;
+        }
+    }
+    len
+}
+
+/// Joins `pieces` with `sep`, starting a new line before a piece that would
+/// go past `width` columns. The pieces start `indent` columns in, and so do
+/// the lines that continue them, after the trimmed separator.
+fn wrap(pieces: &[String], sep: &str, indent: usize, width: usize) -> String {
+    let sep_len = visible_len(sep);
+    let mut text = String::new();
+    let mut column = indent;
+    for (i, piece) in pieces.iter().enumerate() {
+        let len = visible_len(piece);
+        if i == 0 {
+            // The first piece follows whatever is before the chain.
+        } else if column + sep_len + len > width {
+            let sep = sep.trim_start();
+            let start = indent.saturating_sub(visible_len(sep));
+            text += &format!("\n{}{}", " ".repeat(start), sep);
+            column = indent;
+        } else {
+            text += sep;
+            column += sep_len;
+        }
+        text += piece;
+        column += len;
+    }
+    text
+}
+
+impl Function {
+    /// The function as shown in the terminal, with the text of each location
+    /// passed through `link`, along with its line and column, so it can be
+    /// made a hyperlink. `lines` are the lines of the file it is in, if they
+    /// could be read.
+    fn render(
+        &self,
+        link: &dyn Fn(usize, usize, String) -> String,
+        lines: &[&str],
+        layout: Layout,
+    ) -> String {
+        let mut head = format!(
+            "  {} {} {:<15}",
+            theme::paint("line:", Element::Line),
+            aligned_location(self.loc, self.col, link, |loc| {
+                theme::paint(loc, Element::Line)
+            }),
+            theme::paint(&self.full_name(), Element::Function)
+        );
+
+        if let Some(item) = self.item {
+            head += &format!(" {}", item.dimmed());
+        }
+
+        if self.is_async {
+            head += &format!(" {}", "async".dimmed());
+        }
+
+        if self.is_test {
+            head += &format!(" {}", "test".dimmed());
+        }
+
+        if self.is_doctest {
+            head += &format!(" {}", "doctest".dimmed());
+        }
+
+        if let Some((file, line, col)) = &self.origin {
+            let origin = format!("(expanded from {}:{}:{})", file, line, col);
+            head += &format!(" {}", origin.dimmed());
+        }
+
+        if let Some(file) = &self.file {
+            head += &format!(" {}", format!("(in {})", file).dimmed());
+        }
+
+        // The longer the function, the harder a shadow is to follow.
+        let extent = format!("lines {}–{} ({} LOC)", self.loc, self.end, self.lines());
+        let counts = format!(
+            "({}, {})",
+            plural(self.shadowed_variables(), "variable"),
+            plural(self.shadows(), "shadow")
         );
+        let density = format!("({:.1} shadows per 100 lines)", self.density());
+        head += &format!(
+            " {} {} {}",
+            extent.dimmed(),
+            counts.dimmed(),
+            density.dimmed()
+        );
+
+        // The names are as wide as the longest one, up to the maximum, so long
+        // names aren't cut off when there is room for them.
+        let vars = self.vars.iter();
+        let names = vars
+            .filter(|(_, count)| count.locs.iter().any(Case::is_reported))
+            .map(|(ident, _)| ident)
+            .chain(self.items.iter().map(|item| &item.ident));
+        let longest = names.map(|ident| ident_name(ident).chars().count()).max();
+        let name_width = longest
+            .unwrap_or(0)
+            .max(15)
+            .min(layout.max_name_width.max(1));
+        // The chain starts after the name, the count and the `@`.
+        let indent = name_width + 15;
 
         let mut functions = String::from("");
-        for (key, val) in vars.iter() {
-            if val.locs.len() != 1 {
+        for (key, val) in self.sorted_vars() {
+            // Bindings in sibling scopes (e.g. two closures) are all originals.
+            if val.locs.iter().any(Case::is_reported) {
+                let locs: Vec<&Case> = val.locs.iter().filter(|case| !case.is_allowed).collect();
+                let cases: Vec<String> = locs
+                    .iter()
+                    .map(|case| {
+                        let mut shown = match (layout.markers, case.is_original) {
+                            (false, _) => String::new(),
+                            (true, true) => "*".into(),
+                            (true, false) => "→".into(),
+                        };
+                        shown += &format!("{:?}", case);
+                        if let Some(init) = &case.init {
+                            shown += &format!(" {}", format!("({})", init).dimmed());
+                        }
+                        for note in Note::of_shown(case, &val.locs) {
+                            shown += &format!(" {}", note.colored());
+                        }
+                        shown
+                    })
+                    .collect();
+                // The marker of each shadow already points at it.
+                let arrow = if layout.markers {
+                    " ".into()
+                } else {
+                    format!(" {} ", "→".dimmed())
+                };
+                let chain = match layout.width {
+                    Some(width) => wrap(&cases, &arrow, indent, width),
+                    None => cases.join(&arrow),
+                };
                 functions += &format!(
-                    "    {:<15.15} {:>5} {} {:?}\n",
-                    key.to_string().bright_white().bold(),
-                    val.locs.len().to_string().bright_cyan().bold(),
+                    "    {:<width$} {:>7} {} {}",
+                    theme::paint(&fit(&ident_name(key), name_width), Element::Name).bold(),
+                    theme::paint(&locs.len().to_string(), Element::Count).bold(),
                     "@".dimmed(),
-                    val.locs
+                    chain,
+                    width = name_width
                 );
+
+                if locs.iter().any(|case| case.in_macro) {
+                    functions += &format!(" {}", "inside macro (heuristic)".dimmed());
+                }
+                functions += "\n";
+
+                // The first binding, so a shadow far below it can be compared
+                // without opening the file. Arguments and patterns have no
+                // `let` statement, their whole line is shown instead.
+                let original = locs.iter().find(|case| case.is_original);
+                if let Some(original) = original.filter(|_| locs.iter().any(|c| !c.is_original)) {
+                    let text = original.snippet.clone().or_else(|| {
+                        let line = lines.get(original.loc.wrapping_sub(1))?;
+                        Some(shorten(line))
+                    });
+                    if let Some(text) = text {
+                        let loc =
+                            aligned_location(original.loc, original.col, link, |loc| loc.normal());
+                        let detail = format!("{}   (original)", text);
+                        functions += &format!(
+                            "    {:<width$} {}   {}\n",
+                            "",
+                            loc,
+                            detail.dimmed(),
+                            width = name_width
+                        );
+                    }
+                }
+
+                // The statement and scopes of each shadow, below the chain so
+                // it stays on one line.
+                for case in locs.iter().filter(|case| !case.is_original) {
+                    let mut detail = format!("(in {})", case.scope);
+                    if let Some(snippet) = &case.snippet {
+                        detail = format!("{}   {}", snippet, detail);
+                    }
+                    // Code that is only compiled on some configurations may
+                    // only matter to some people.
+                    for cfg in &case.cfgs {
+                        detail += &format!(" (cfg({}))", cfg);
+                    }
+                    let loc = aligned_location(case.loc, case.col, link, |loc| loc.normal());
+                    functions += &format!(
+                        "    {:<width$} {}   {}\n",
+                        "",
+                        loc,
+                        detail.dimmed(),
+                        width = name_width
+                    );
+                }
             }
         }
 
-        write!(fmt, "{}\n{}", head, functions)
+        for item in &self.items {
+            functions += &format!(
+                "    {:<width$} {:>7} {} {:?}\n",
+                theme::paint(&fit(&ident_name(&item.ident), name_width), Element::Name).bold(),
+                theme::paint(&item.kind.to_string(), Element::Item).bold(),
+                "@".dimmed(),
+                item.locs,
+                width = name_width
+            );
+        }
+
+        format!("{}\n{}", head, functions)
+    }
+}
+
+/// Settings that change which bindings are counted as shadows.
+#[derive(Default, Clone, Debug)]
+pub struct Options {
+    /// Also count narrowing a variable, like `let x = x?;`, `let x = x.unwrap();`
+    /// or `if let Some(x) = x`, as a shadow.
+    strict: bool,
+    /// Also report locals that reuse the name of a `const` or `static` item.
+    check_items: bool,
+    /// Also report locals in a method that reuse the name of a field of
+    /// `self`, like `let data = ..;` next to `self.data`.
+    check_fields: bool,
+    /// Try to parse the tokens of macro invocations as statements or
+    /// expressions, and analyze them like regular code.
+    macros: bool,
+    /// Look for names bound more than once by `let` in the arms of
+    /// `macro_rules!` definitions.
+    macro_rules: bool,
+    /// Only report shadows of this classification.
+    class: Option<ShadowClass>,
+    /// Don't report ownership conversions, like `let x = x.clone();`.
+    ignore_conversions: bool,
+    /// Report `let x = x;` style rebindings, which are allowed by default.
+    report_rebinds: bool,
+    /// Skip bindings whose name starts with `_`, which are often re-declared on
+    /// purpose, like `_guard`.
+    ignore_underscore: bool,
+    /// Don't report test functions and functions in `#[cfg(test)]` modules.
+    skip_tests: bool,
+    /// Only report test functions and functions in `#[cfg(test)]` modules.
+    only_tests: bool,
+    /// Only report variables that are shadowed at least this many times in a
+    /// function.
+    min_depth: usize,
+    /// Only report shadows at least this many lines after the end of the
+    /// statement they shadow.
+    min_distance: usize,
+    /// Only report functions with at least this many shadows per 100 lines.
+    min_density: f64,
+    /// Also report `ref` and `ref mut` bindings, like `let ref x = y;`.
+    include_ref_patterns: bool,
+    /// Also report names that are reused in sibling scopes of a function, which
+    /// isn't shadowing but can be just as confusing.
+    sibling_reuse: bool,
+    /// Also analyze the Rust code blocks in doc comments.
+    include_doctests: bool,
+}
+
+/// Where a set of new bindings comes from.
+#[derive(Clone, Default)]
+struct Binding<'e> {
+    // The expression the bound values come from, if any.
+    init: Option<&'e Expr>,
+    // `let x = x;`, which only moves `x` (or changes its mutability).
+    is_rebind: bool,
+    // The pattern of an `if let` or `while let`, which may narrow the matched
+    // variable, like `if let Some(x) = x`.
+    is_conditional: bool,
+    // The pattern of a `for` loop.
+    is_loop: bool,
+    // An argument of the function, or of a closure.
+    is_arg: bool,
+    is_closure_arg: bool,
+    // A match arm pattern that is only the scrutinee's name.
+    rebinds_scrutinee: bool,
+    // The variable that a `for` loop iterates over, if any.
+    iterated: Option<Ident>,
+    // Where the bound values come from, when it isn't classified from `init`.
+    source: Option<InitKind>,
+    // The statement's source, as shown in reports.
+    snippet: Option<String>,
+    // The type of a pattern that binds a single variable, if it is known.
+    ty: Option<String>,
+    // The whole `let` statement, or the pattern when the bindings aren't made
+    // by a statement.
+    span: Option<Span>,
+}
+
+impl<'e> Binding<'e> {
+    fn new(init: Option<&'e Expr>) -> Self {
+        Binding {
+            init,
+            ..Binding::default()
+        }
     }
 }
 
-#[derive(Default)]
+/// A function that is currently being visited.
+struct Frame {
+    // Index of the function in `ShadowCounter::funcs`.
+    func: usize,
+    // The names bound in each scope of the function that is live at the current
+    // point of the traversal, innermost last. A binding only shadows names found here.
+    // Each name is stored with the index of its binding in the variable's `locs`.
+    scopes: Vec<Vec<(Ident, usize)>>,
+    // The index in `scopes` of the scope of each closure being visited,
+    // innermost last. Names bound in an outer scope are captured by the closure.
+    closures: Vec<usize>,
+    // What made each scope in `scopes`, like `closure` or `match arm`. Scopes
+    // of blocks that are part of another construct, like a function body or
+    // the body of a loop, have none.
+    labels: Vec<Option<&'static str>>,
+    // How many `unsafe` blocks are being visited.
+    unsafe_blocks: usize,
+    // How many `.await`s have been visited in the function so far.
+    awaits: usize,
+    // The fields of `self`, when the function is a method of a struct declared
+    // in the file and fields are checked.
+    fields: Vec<Ident>,
+}
+
 pub struct ShadowCounter<'a> {
     funcs: Vec<Function>,
     filename: &'a str,
-    has_shadow: bool,
+    options: &'a Options,
+    // Functions can be nested inside of each other, innermost last. Each one
+    // has its own scopes, an inner `fn` can't see the outer function's locals.
+    frames: Vec<Frame>,
+    // The items declared in each module being visited, innermost last. Only the
+    // innermost module's items are in scope without a `use`.
+    modules: Vec<Vec<NamedItem>>,
+    // Whether the traversal is inside of a re-parsed macro invocation.
+    in_macro: bool,
+    // How many of the modules being visited are `#[cfg(test)]`.
+    test_modules: usize,
+    // The variants of every enum in the file being visited, by enum name.
+    enums: HashMap<String, Vec<Ident>>,
+    // The named fields of every struct in the file being visited, by struct
+    // name.
+    structs: HashMap<String, Vec<Ident>>,
+    // The files pulled in with `include!` or `#[path]` that are being visited,
+    // innermost last.
+    includes: Vec<PathBuf>,
+    // The names of the modules being visited, outermost first.
+    mod_path: Vec<String>,
+    // The type (and trait) of the `impl` or `trait` block being visited.
+    owner: Option<(String, Option<String>)>,
+    // The predicates of the `#[cfg(..)]` attributes on the items, blocks and
+    // statements being visited, outermost first.
+    cfgs: Vec<String>,
 }
 
 impl<'a> ShadowCounter<'a> {
-    fn new(filename: &'a str) -> Self {
+    fn new(filename: &'a str, options: &'a Options) -> Self {
         ShadowCounter {
             filename,
+            options,
             funcs: Vec::new(),
-            has_shadow: false,
+            frames: Vec::new(),
+            modules: Vec::new(),
+            in_macro: false,
+            test_modules: 0,
+            enums: HashMap::new(),
+            structs: HashMap::new(),
+            includes: Vec::new(),
+            mod_path: Vec::new(),
+            owner: None,
+            cfgs: Vec::new(),
         }
     }
-}
 
-/// Gets the identifiers from a Punctuated pattern.
-/// Doesn't yet work as intended. Can only get a single identifer, like:
-/// let a = 5; Will not work with let (a, b) = 5;
-fn get_idents(pattern: &Punctuated<Pat, Or>) -> Vec<Ident> {
-    let mut idents = Vec::<Ident>::new();
-    for p in pattern {
-        match p {
-            Pat::Ident(i) => {
-                // if i.by_ref.is_none() {
-                idents.push(i.ident.clone());
-                // }
-            }
-            _ => continue,
-        }
+    /// Adds the `#[cfg(..)]` predicates of `attrs` to the ones being visited,
+    /// returning how many there were before, to truncate them to afterwards.
+    fn push_cfgs(&mut self, attrs: &[Attribute]) -> usize {
+        let before = self.cfgs.len();
+        self.cfgs.extend(cfg_predicates(attrs));
+        before
     }
-    return idents;
-}
 
-impl<'ast, 'a> visit::Visit<'ast> for ShadowCounter<'a> {
-    fn visit_item_fn(&mut self, i: &ItemFn) {
-        // println!("{}", i.ident.to_string());
-        self.funcs.push(Function::new(
-            i.ident.to_string(),
-            i.ident.span().start().line,
-        ));
-        // self.current_func = i.ident.clone();
-        visit::visit_item_fn(self, i);
+    /// The directory that paths in the file being visited are relative to.
+    fn source_dir(&self) -> PathBuf {
+        let file = match self.includes.last() {
+            Some(path) => path.as_path(),
+            None => Path::new(self.filename),
+        };
+        file.parent()
+            .unwrap_or_else(|| Path::new("."))
+            .to_path_buf()
     }
 
-    fn visit_impl_item_method(&mut self, i: &'ast ImplItemMethod) {
-        // println!("{}", i.sig.ident.to_string());
-        self.funcs.push(Function::new(
-            i.sig.ident.to_string(),
-            i.sig.ident.span().start().line,
-        ));
-        // self.current_func = i.ident.clone();
-        visit::visit_impl_item_method(self, i);
+    /// Parses a file pulled in with `include!` or `#[path]` and visits its
+    /// items with `visit`, as part of the file being visited. A file that is
+    /// already being visited (an include cycle) is skipped.
+    fn visit_included<F: FnOnce(&mut Self, &File)>(&mut self, path: PathBuf, visit: F) {
+        let canonical = path.canonicalize().ok();
+        let is_cycle = Path::new(self.filename).canonicalize().ok() == canonical
+            || self
+                .includes
+                .iter()
+                .any(|p| p.canonicalize().ok() == canonical);
+        if is_cycle {
+            return;
+        }
+
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(_) => {
+                eprintln!("{}: {}\n", "Unable to read".red(), path.display());
+                return;
+            }
+        };
+        let syntax = match parse_source(&source) {
+            Ok(syntax) => syntax,
+            Err(_) => {
+                eprintln!("{}: {}\n", "Unable to parse".red(), path.display());
+                return;
+            }
+        };
+
+        self.enums.extend(get_enums(&syntax));
+        self.structs.extend(get_structs(&syntax));
+        self.includes.push(path);
+        visit(self, &syntax);
+        self.includes.pop();
     }
 
-    fn visit_local(&mut self, i: &Local) {
-        // println!("{:?}", i);
+    /// Analyzes the doctests in the doc comments of `file`, with locations in
+    /// `file`.
+    fn visit_doctests(&mut self, file: &File) {
+        for doctest in doctest::extract(file) {
+            let syntax = match parse_source(&doctest.source) {
+                Ok(syntax) => syntax,
+                Err(_) => {
+                    let at = format!("doctest at {}:{}", self.filename, doctest.line);
+                    eprintln!("{}: {}\n", "Unable to parse".red(), at);
+                    continue;
+                }
+            };
 
-        // Get the possible identifiers.
-        let ids = get_idents(&i.pats);
-        {
-            // Because the tree is traversed function first and then its local bindings,
-            // the last_mut() of the vec of functions is the surrounding scope of the current
-            // local binding. Therefore, the last function contains the identifier map.
-            let func_counter: Option<&mut Function> = self.funcs.last_mut();
-
-            // Every local binding should be within a function/impl method (?).
-            if func_counter.is_none() {
-                panic!(
-                    "Local without a function? line: {}",
-                    ids.get(0).unwrap().span().start().line
-                );
+            let first = self.funcs.len();
+            self.visit_file(&syntax);
+            for func in &mut self.funcs[first..] {
+                func.is_doctest = true;
+                func.map_locations(|line, col| doctest.map(line, col));
             }
+        }
+    }
 
-            let func_counter = func_counter.unwrap(); // Guaranteed to not crash here.
+    /// Reports the names that are bound by `let` more than once in an arm of a
+    /// `macro_rules!` definition, as they shadow at every expansion. The arms
+    /// are only tokens, so any `let <name>` counts, whatever block it is in.
+    fn scan_macro_rules(&mut self, i: &ItemMacro) {
+        let name = match &i.ident {
+            Some(name) => name,
+            None => return,
+        };
+        let is_test = self.test_modules > 0;
+        if (self.options.skip_tests && is_test) || (self.options.only_tests && !is_test) {
+            return;
+        }
 
-            for i in ids {
-                let line = i.span().start().line;
-                let count = func_counter.vars.entry(i).or_insert(Count::new());
+        let mut func = Function {
+            item: Some("potential shadows in macro definition"),
+            path: self.mod_path.clone(),
+            module: self.mod_path.clone(),
+            file: self.includes.last().map(|path| path.display().to_string()),
+            is_test,
+            end: i.span().end().line,
+            ..Function::new(name.to_string(), name.span().start(), false)
+        };
 
-                let is_original: bool = count.locs.len() == 0;
-                count.locs.push(Case::new(line, is_original));
+        // Arms are `(matcher) => { transcriber }`, separated by `;`.
+        let trees: Vec<TokenTree> = i.mac.tokens.clone().into_iter().collect();
+        let arms = trees.windows(4).filter_map(|arm| match arm {
+            [TokenTree::Group(_), TokenTree::Punct(eq), TokenTree::Punct(gt), TokenTree::Group(body)]
+                if eq.as_char() == '=' && gt.as_char() == '>' =>
+            {
+                Some(body)
+            }
+            _ => None,
+        });
 
-                if !is_original {
-                    func_counter.has_shadow = true;
-                    self.has_shadow = true;
+        for (n, body) in arms.enumerate() {
+            let mut arm: HashMap<Ident, Count> = HashMap::new();
+            for ident in let_names(body.stream()) {
+                let count = arm.entry(ident.clone()).or_default();
+                // Uses can't be told apart from the rest of the tokens.
+                count.locs.push(Case {
+                    in_macro: true,
+                    is_used: true,
+                    shadows: count.locs.len().checked_sub(1),
+                    scope: format!("macro_rules! {} > arm {}", name, n + 1),
+                    ..Case::new(ident.span().start(), count.locs.is_empty())
+                });
+            }
+            // Names repeated in several arms are listed together, so the
+            // index of each shadowed binding moves along.
+            for (ident, count) in arm {
+                if count.locs.len() > 1 {
+                    let locs = &mut func.vars.entry(ident).or_default().locs;
+                    let offset = locs.len();
+                    locs.extend(count.locs.into_iter().map(|case| Case {
+                        shadows: case.shadows.map(|index| index + offset),
+                        ..case
+                    }));
                 }
             }
         }
 
-        visit::visit_local(self, i);
+        func.has_shadow = !func.vars.is_empty();
+        self.funcs.push(func);
     }
-}
 
-fn print_visitor(counter: ShadowCounter) {
-    println!("{} contains shadowed variable(s):\n", counter.filename);
-    for f in counter.funcs {
-        if f.has_shadow {
-            println!("{}", f);
+    /// Starts tracking a new function, whose arguments are its first bindings.
+    /// `end` is where the function's body ends.
+    fn enter_function(&mut self, sig: &Signature, attrs: &[Attribute], end: LineColumn) {
+        self.enter_body(&sig.ident, attrs, end);
+        if let Some(func) = self.funcs.last_mut() {
+            func.is_async = sig.asyncness.is_some();
+        }
+
+        // A `let` reusing one of the argument names is a shadow.
+        for (arg, ty) in get_arg_idents(sig) {
+            let binding = Binding {
+                ty: ty.and_then(type_name),
+                is_arg: true,
+                source: Some(InitKind::Argument),
+                ..Binding::default()
+            };
+            self.bind(vec![arg], binding);
         }
     }
-}
 
-fn main() {
-    // println!("{}", Startom)
-    let matches = App::new("cargo-light")
-        .about("Finds and prints potential usages of shadowed variables.")
-        .author("Fisher Darling <fdarlingco@gmail.com>")
-        .version("0.1.0")
-        .bin_name("cargo")
-        .subcommand(
-            SubCommand::with_name("light")
-                .arg(
-                    Arg::with_name("files")
-                        .short("F")
-                        .long("files")
-                        .takes_value(true)
-                        .multiple(true)
-                        .help("Files to be parsed (can accept a glob)."),
-                )
-                .arg(
-                    Arg::with_name("dir")
-                        .short("d")
-                        .long("directory")
-                        .takes_value(true)
-                        .multiple(false)
-                        .help("Directory to walk and parse."),
-                ),
-        )
-        .get_matches();
+    /// Starts tracking the initializer of a `const` or `static` item, whose
+    /// blocks can have locals too, like `const X: T = { let x = ..; .. };`.
+    fn enter_item(
+        &mut self,
+        kind: &'static str,
+        ident: &Ident,
+        attrs: &[Attribute],
+        end: LineColumn,
+    ) {
+        self.enter_body(ident, attrs, end);
+        if let Some(func) = self.funcs.last_mut() {
+            func.item = Some(kind);
+        }
+    }
 
-    if let Some(files) = matches
-        .subcommand_matches("light")
-        .unwrap()
-        .values_of("files")
-    {
-        for file in files {
-            let source = fs::read_to_string(file).unwrap();
-            let syntax = syn::parse_file(&source).expect("Unable to parse file");
+    /// Starts tracking a function, or another body of code, named `ident`.
+    fn enter_body(&mut self, ident: &Ident, attrs: &[Attribute], end: LineColumn) {
+        // Helpers nested inside of a test are test code too.
+        let in_test = self.test_modules > 0
+            || self
+                .frames
+                .last()
+                .is_some_and(|frame| self.funcs[frame.func].is_test);
 
-            let mut visitor = ShadowCounter::new(file);
+        // A function nested inside of another one is named after it, to tell
+        // apart helpers with the same name in different functions.
+        let path = match self.frames.last() {
+            Some(frame) => vec![self.funcs[frame.func].full_name()],
+            None => self.mod_path.clone(),
+        };
 
-            visit::visit_file(&mut visitor, &syntax);
-            print_visitor(visitor);
-        }
-    } else if let Some(dir) = matches
-        .subcommand_matches("light")
-        .unwrap()
-        .value_of("dir")
-        .or(Some("."))
-    {
-        let walker = WalkDir::new(dir).into_iter();
+        self.funcs.push(Function {
+            is_test: in_test || is_test_attr(attrs),
+            path,
+            module: self.mod_path.clone(),
+            file: self.includes.last().map(|path| path.display().to_string()),
+            end: end.line,
+            ..Function::new(ident.to_string(), ident.span().start(), false)
+        });
+        self.frames.push(Frame {
+            func: self.funcs.len() - 1,
+            scopes: vec![Vec::new()],
+            labels: vec![None],
+            closures: Vec::new(),
+            unsafe_blocks: 0,
+            awaits: 0,
+            fields: Vec::new(),
+        });
+    }
 
-        for file in walker {
-            let file = file.expect("Unable to parse file name.");
+    /// Marks the function just entered, with signature `sig` (none for an
+    /// associated const), as a method of the `impl` or `trait` block being
+    /// visited.
+    fn set_owner(&mut self, sig: Option<&Signature>) {
+        if let Some(func) = self.funcs.last_mut() {
+            func.owner = self.owner.clone();
+        }
 
-            if !is_file_with_ext(&file, "rs") {
-                // Not a .rs file
-                continue;
+        // The arguments are already bound, a `fn set(&mut self, data: T)`
+        // naming its argument after the field it sets is the norm.
+        let fields = match &self.owner {
+            Some((ty, _))
+                if self.options.check_fields && sig.is_some_and(|sig| sig.receiver().is_some()) =>
+            {
+                self.structs.get(ty).cloned().unwrap_or_default()
             }
+            _ => Vec::new(),
+        };
+        if let Some(frame) = self.frames.last_mut() {
+            frame.fields = fields;
+        }
+    }
 
-            let file = file.path().to_str();
-            // println!("{:?}", file);
+    /// Stops tracking the innermost function, the bindings that follow belong
+    /// to the function surrounding it (if any).
+    fn exit_function(&mut self) {
+        let frame = match self.frames.pop() {
+            Some(frame) => frame,
+            None => return,
+        };
+        let func = &mut self.funcs[frame.func];
 
-            if file.is_none() {
-                eprintln!("Unable to parse a file.");
-                continue;
+        // Thresholds depend on a variable's whole history, so they are applied
+        // once the function is done.
+        for count in func.vars.values_mut() {
+            let depth = count.locs.iter().filter(|case| case.is_reported()).count();
+            if depth < self.options.min_depth {
+                for case in &mut count.locs {
+                    case.is_allowed |= case.is_reported();
+                }
             }
+        }
 
-            let file = file.unwrap();
+        func.has_shadow |= func
+            .vars
+            .values()
+            .any(|count| count.locs.iter().any(Case::is_reported));
+        func.has_shadow &= func.density() >= self.options.min_density;
+    }
 
-            let source = fs::read_to_string(file).unwrap();
-            let syntax = syn::parse_file(&source);
+    /// Marks the binding that `name` currently refers to as used.
+    fn mark_used(&mut self, name: &str) {
+        let frame = match self.frames.last() {
+            Some(frame) => frame,
+            None => return,
+        };
 
-            if syntax.is_err() {
-                eprintln!("{}: {}\n", "Unable to parse".red(), file);
-                continue;
+        let bound = frame
+            .scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(bound, _)| bound == name);
+
+        if let Some((ident, index)) = bound {
+            if let Some(count) = self.funcs[frame.func].vars.get_mut(ident) {
+                count.locs[*index].is_used = true;
             }
+        }
+    }
 
-            let syntax = syntax.unwrap();
-            let mut visitor = ShadowCounter::new(file);
-            visit::visit_file(&mut visitor, &syntax);
+    /// Visits the body of a closure, match arm or `const` item, whose block
+    /// (if it is one) is part of it and doesn't get a scope label of its own.
+    fn visit_body(&mut self, body: &Expr) {
+        match body {
+            Expr::Block(block) if block.label.is_none() => self.visit_block(&block.block),
+            body => self.visit_expr(body),
+        }
+    }
 
-            if visitor.has_shadow {
-                print_visitor(visitor);
-            }
+    fn push_scope(&mut self, label: Option<&'static str>) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.scopes.push(Vec::new());
+            frame.labels.push(label);
+        }
+    }
+
+    fn pop_scope(&mut self) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.scopes.pop();
+            frame.labels.pop();
+        }
+    }
+
+    /// How many `.await`s have been visited in the function so far.
+    fn awaits(&self) -> usize {
+        self.frames.last().map_or(0, |frame| frame.awaits)
+    }
+
+    /// Forgets the `.await`s visited since `awaits` were, like the ones of a
+    /// future that doesn't run in between.
+    fn restore_awaits(&mut self, awaits: usize) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.awaits = awaits;
+        }
+    }
+
+    /// The identifiers that a refutable pattern, like a match arm or the
+    /// pattern of an `if let`, binds. A bare name like `None` or `Empty` is a
+    /// path the value is compared against, not a new local.
+    fn pattern_bindings<'p>(&self, pat: &'p Pat) -> Vec<&'p PatIdent> {
+        get_idents(pat)
+            .into_iter()
+            .filter(|pat| !self.is_path_pattern(pat))
+            .collect()
+    }
+
+    fn is_path_pattern(&self, pat: &PatIdent) -> bool {
+        if pat.by_ref.is_some() || pat.mutability.is_some() || pat.subpat.is_some() {
+            return false;
+        }
+        let ident = pat.ident.unraw();
+        // A variant, unit struct, constant or static in scope is compared
+        // against, whatever its name looks like.
+        let items = self.modules.last().map(Vec::as_slice).unwrap_or(&[]);
+        let names_item = items.iter().any(|item| {
+            item.ident.unraw() == ident
+                && matches!(
+                    item.kind,
+                    ItemKind::Variant | ItemKind::UnitStruct | ItemKind::Const | ItemKind::Static
+                )
+        });
+        let name = ident.to_string();
+        // Locals are snake case, while variants, unit structs and constants
+        // start with an uppercase letter.
+        names_item
+            || PRELUDE_VARIANTS.contains(&name.as_str())
+            || name.starts_with(|c: char| c.is_uppercase())
+    }
+
+    /// Records a new binding of each of `ids` in the current function.
+    fn bind(&mut self, ids: Vec<&PatIdent>, binding: Binding) {
+        // Bindings outside of a function, like in the initializer of a `const`,
+        // aren't tracked.
+        let frame = match self.frames.last_mut() {
+            Some(frame) => frame,
+            None => return,
+        };
+        let func_counter = &mut self.funcs[frame.func];
+
+        if (self.options.skip_tests && func_counter.is_test)
+            || (self.options.only_tests && !func_counter.is_test)
+        {
+            return;
+        }
+
+        for pat in ids {
+            // `r#foo` and `foo` are the same name, so names are tracked without
+            // the `r#` prefix.
+            let i = pat.ident.unraw();
+            if self.options.ignore_underscore && i.to_string().starts_with('_') {
+                continue;
+            }
+
+            let start = i.span().start();
+            let shadowed = frame
+                .scopes
+                .iter()
+                .enumerate()
+                .rev()
+                .flat_map(|(depth, scope)| scope.iter().rev().map(move |b| (depth, b)))
+                .find(|(_, (bound, _))| *bound == i)
+                .map(|(depth, &(_, index))| (depth, index));
+            let shadows = shadowed.map(|(_, index)| index);
+            let is_original = shadows.is_none();
+            let shadows_capture = match (shadowed, frame.closures.last()) {
+                (Some((depth, _)), Some(&closure)) => depth < closure,
+                _ => false,
+            };
+
+            let count = func_counter.vars.entry(i.clone()).or_default();
+            if let Some(scope) = frame.scopes.last_mut() {
+                scope.push((i.clone(), count.locs.len()));
+            }
+            // The name was bound before, in a scope that has ended since.
+            let reuses = match count.locs.len() {
+                n if n > 0 && is_original && self.options.sibling_reuse => Some(n - 1),
+                _ => None,
+            };
+
+            // Shadowed imports, variants, unit structs and functions are always
+            // reported, constants and statics only on request.
+            let items = self.modules.last().map(Vec::as_slice).unwrap_or(&[]);
+            let check_items = self.options.check_items;
+            let item = items.iter().find(|item| {
+                item.ident.unraw() == i
+                    && (!matches!(item.kind, ItemKind::Const | ItemKind::Static) || check_items)
+            });
+            let field = frame.fields.iter().find(|field| field.unraw() == i);
+            let item = match (item, field) {
+                (Some(item), _) => Some((item.kind, &item.ident)),
+                (None, Some(field)) => Some((ItemKind::Field, field)),
+                (None, None) => None,
+            };
+
+            if let Some((kind, ident)) = item {
+                func_counter.items.push(ItemShadow {
+                    ident: i.clone(),
+                    kind,
+                    locs: vec![
+                        Case::new(ident.span().start(), true),
+                        Case::new(start, false),
+                    ],
+                });
+                func_counter.has_shadow = true;
+            }
+
+            let changes_apparent_type = match (shadows, &binding.ty) {
+                (Some(index), Some(ty)) => func_counter.vars[&i].locs[index]
+                    .ty
+                    .as_ref()
+                    .is_some_and(|shadowed| types_differ(shadowed, ty)),
+                _ => false,
+            };
+
+            let item = func_counter.item.unwrap_or("fn");
+            let scope: Vec<String> = std::iter::once(format!("{} {}", item, func_counter.name))
+                .chain(frame.labels.iter().flatten().map(|label| label.to_string()))
+                .collect();
+
+            let stmt = binding.span.unwrap_or_else(|| i.span());
+            let mut case = Case {
+                stmt: Some((stmt.start(), stmt.end())),
+                ty: binding.ty.clone(),
+                init: binding
+                    .source
+                    .clone()
+                    .or_else(|| binding.init.map(InitKind::of)),
+                snippet: binding.snippet.clone(),
+                scope: scope.join(" > "),
+                cfgs: self.cfgs.clone(),
+                awaits: frame.awaits,
+                changes_apparent_type,
+                shadows,
+                reuses,
+                is_mut: pat.mutability.is_some(),
+                is_loop_var: binding.is_loop,
+                shadows_capture,
+                is_arg: binding.is_arg,
+                is_closure_arg: binding.is_closure_arg,
+                rebinds_scrutinee: binding.rebinds_scrutinee,
+                shadows_iterable: binding.iterated.as_ref() == Some(&i),
+                in_unsafe: frame.unsafe_blocks > 0,
+                in_macro: self.in_macro,
+                ..Case::new(start, is_original)
+            };
+
+            // The distance is counted from the end of the shadowed statement, so
+            // a long `let` right before its shadow is still close to it.
+            if let Some(index) = shadows {
+                let distance = case.distance_from(&func_counter.vars[&i].locs[index]);
+                case.is_allowed |= distance < self.options.min_distance;
+            }
+
+            if !is_original {
+                let class = match binding.init {
+                    Some(init) if is_conversion(init, &i) => ShadowClass::Conversion,
+                    Some(init) if expr_uses(init, &i) => ShadowClass::Derived,
+                    _ => ShadowClass::Unrelated,
+                };
+                case.class = Some(class);
+                // Unless strict, narrowed variables are brought into scope
+                // without counting as a shadow.
+                let is_narrowing = binding.init.is_some_and(|init| {
+                    is_narrowing(init, &i)
+                        || (binding.is_conditional && expr_ident(init).as_ref() == Some(&i))
+                });
+                // A `ref` binding still hides the name, but it is only reported
+                // on request.
+                let is_excluded_ref = pat.by_ref.is_some() && !self.options.include_ref_patterns;
+                case.is_allowed |= is_excluded_ref
+                    || self.options.class.is_some_and(|only| only != class)
+                    || (binding.is_rebind && !self.options.report_rebinds)
+                    || (is_narrowing && !self.options.strict)
+                    || (class == ShadowClass::Conversion && self.options.ignore_conversions);
+            }
+
+            func_counter.vars.entry(i).or_default().locs.push(case);
+        }
+    }
+}
+
+/// Gets the identifiers bound by a pattern.
+/// Walks arbitrarily nested patterns, so `let a = 5;` yields `a` and
+/// `let (&a, Point { x, y: Some(b) }) = ...;` yields `a`, `x` and `b`.
+fn get_idents(pat: &Pat) -> Vec<&PatIdent> {
+    let mut idents = Vec::new();
+    pat_idents(pat, &mut idents);
+    idents
+}
+
+/// Pushes every identifier bound by `pat` onto `idents`.
+fn pat_idents<'p>(pat: &'p Pat, idents: &mut Vec<&'p PatIdent>) {
+    match pat {
+        Pat::Ident(i) => {
+            idents.push(i);
+
+            // `whole @ Some(part)` binds both sides of the `@`, while the
+            // subslice binding `rest @ ..` only binds `rest`.
+            if let Some((_, subpat)) = &i.subpat {
+                pat_idents(subpat, idents);
+            }
+        }
+        // `let x: u8 = ...;` and typed closure arguments.
+        Pat::Type(t) => pat_idents(&t.pat, idents),
+        Pat::Tuple(t) => elems_idents(&t.elems, idents),
+        // `Some(val)`, `Wrapper(inner)`, etc.
+        Pat::TupleStruct(t) => elems_idents(&t.elems, idents),
+        Pat::Struct(s) => {
+            // Shorthand fields (`Point { x, .. }`) are parsed as `x: x`, so the
+            // bound name is always the field's pattern, never its member.
+            for field in &s.fields {
+                pat_idents(&field.pat, idents);
+            }
+        }
+        Pat::Slice(s) => elems_idents(&s.elems, idents),
+        Pat::Reference(r) => pat_idents(&r.pat, idents),
+        Pat::Paren(p) => pat_idents(&p.pat, idents),
+        Pat::Or(or) => {
+            // Or-patterns, like `Ok(x) | Err(x)`, must bind the same names in
+            // every alternative, so each name is only reported once (from the
+            // first alternative that binds it).
+            let mut bound = Vec::new();
+            for case in &or.cases {
+                let mut alternative = Vec::new();
+                pat_idents(case, &mut alternative);
+
+                for i in alternative {
+                    if !bound.iter().any(|b: &&PatIdent| b.ident == i.ident) {
+                        bound.push(i);
+                    }
+                }
+            }
+            idents.extend(bound);
+        }
+        // Wildcards, rests, literals, ranges, paths and macros don't bind
+        // anything. Unstable `box` patterns are only available as verbatim tokens.
+        _ => {}
+    }
+}
+
+/// Gets the identifiers bound by a function's arguments, like `input` in
+/// `fn f(input: &str)` or `x` and `y` in `fn f((x, y): Point)`, with their
+/// types when the argument is simply named. `self` is never shadowed by a
+/// `let`, so it is skipped.
+fn get_arg_idents(sig: &Signature) -> Vec<(&PatIdent, Option<&Type>)> {
+    let mut idents = Vec::new();
+    for arg in &sig.inputs {
+        if let FnArg::Typed(PatType { pat, ty, .. }) = arg {
+            match &**pat {
+                Pat::Ident(i) => idents.push((i, Some(&**ty))),
+                // The bindings of a destructuring pattern, like `(x, y): Point`,
+                // whose own types aren't known.
+                pat => idents.extend(get_idents(pat).into_iter().map(|i| (i, None))),
+            }
+        }
+    }
+    idents
+}
+
+/// Gets the items declared directly in a module that locals can shadow.
+/// `enums` are the variants of every enum in the file, by enum name, which
+/// glob imports like `use Direction::*` bring into scope.
+fn get_module_items(items: &[Item], enums: &HashMap<String, Vec<Ident>>) -> Vec<NamedItem> {
+    let mut named = Vec::new();
+    for item in items {
+        match item {
+            Item::Const(c) => named.push(NamedItem {
+                ident: c.ident.clone(),
+                kind: ItemKind::Const,
+            }),
+            Item::Static(s) => named.push(NamedItem {
+                ident: s.ident.clone(),
+                kind: ItemKind::Static,
+            }),
+            Item::Struct(s) if matches!(s.fields, Fields::Unit) => named.push(NamedItem {
+                ident: s.ident.clone(),
+                kind: ItemKind::UnitStruct,
+            }),
+            Item::Fn(f) => named.push(NamedItem {
+                ident: f.sig.ident.clone(),
+                kind: ItemKind::Fn,
+            }),
+            Item::Use(u) => named.extend(use_idents(&u.tree, None, enums)),
+            _ => {}
+        }
+    }
+    named
+}
+
+/// Gets the names a `use` tree brings into scope, like `Result` and `io` for
+/// `use std::io::{self, Result, Write as _}`. `parent` is the path segment
+/// before `tree`, which `self` refers to. Names imported from one of `enums`
+/// are variants, other glob imports are skipped.
+fn use_idents(
+    tree: &UseTree,
+    parent: Option<&Ident>,
+    enums: &HashMap<String, Vec<Ident>>,
+) -> Vec<NamedItem> {
+    let variants = parent.and_then(|p| enums.get(&p.to_string()));
+    let import = |ident: &Ident| {
+        let is_variant = variants.is_some_and(|v| v.contains(ident));
+        NamedItem {
+            ident: ident.clone(),
+            kind: if is_variant {
+                ItemKind::Variant
+            } else {
+                ItemKind::Import
+            },
+        }
+    };
+
+    match tree {
+        UseTree::Path(p) => use_idents(&p.tree, Some(&p.ident), enums),
+        UseTree::Name(n) if n.ident == "self" => parent.into_iter().map(import).collect(),
+        UseTree::Name(n) => vec![import(&n.ident)],
+        UseTree::Rename(r) if r.rename == "_" => Vec::new(),
+        UseTree::Rename(r) => vec![NamedItem {
+            ident: r.rename.clone(),
+            ..import(&r.ident)
+        }],
+        UseTree::Group(g) => g
+            .items
+            .iter()
+            .flat_map(|tree| use_idents(tree, parent, enums))
+            .collect(),
+        UseTree::Glob(_) => variants
+            .into_iter()
+            .flatten()
+            .map(|ident| NamedItem {
+                ident: ident.clone(),
+                kind: ItemKind::Variant,
+            })
+            .collect(),
+    }
+}
+
+/// Gets the variants of every enum declared in a file, by enum name.
+fn get_enums(file: &File) -> HashMap<String, Vec<Ident>> {
+    struct Enums(HashMap<String, Vec<Ident>>);
+
+    impl<'ast> Visit<'ast> for Enums {
+        fn visit_item_enum(&mut self, i: &'ast ItemEnum) {
+            let variants = i.variants.iter().map(|v| v.ident.clone()).collect();
+            self.0.insert(i.ident.to_string(), variants);
+            visit::visit_item_enum(self, i);
+        }
+    }
+
+    let mut enums = Enums(HashMap::new());
+    enums.visit_file(file);
+    enums.0
+}
+
+/// Gets the named fields of every struct declared in a file, by struct name.
+fn get_structs(file: &File) -> HashMap<String, Vec<Ident>> {
+    struct Structs(HashMap<String, Vec<Ident>>);
+
+    impl<'ast> Visit<'ast> for Structs {
+        fn visit_item_struct(&mut self, i: &'ast ItemStruct) {
+            if let Fields::Named(fields) = &i.fields {
+                let names = fields.named.iter().filter_map(|f| f.ident.clone());
+                self.0.insert(i.ident.to_string(), names.collect());
+            }
+            visit::visit_item_struct(self, i);
+        }
+    }
+
+    let mut structs = Structs(HashMap::new());
+    structs.visit_file(file);
+    structs.0
+}
+
+/// The names that follow `let` (or `let mut`) anywhere in `tokens`, in order.
+/// A metavariable like `$name` counts as `name`.
+fn let_names(tokens: proc_macro2::TokenStream) -> Vec<Ident> {
+    let mut names = Vec::new();
+    let mut after_let = false;
+    for tree in tokens {
+        match tree {
+            TokenTree::Ident(i) if i == "let" => after_let = true,
+            TokenTree::Ident(i) if after_let && i == "mut" => {}
+            TokenTree::Punct(p) if after_let && p.as_char() == '$' => {}
+            TokenTree::Ident(i) if after_let => {
+                names.push(i);
+                after_let = false;
+            }
+            TokenTree::Group(g) => {
+                names.extend(let_names(g.stream()));
+                after_let = false;
+            }
+            _ => after_let = false,
+        }
+    }
+    names
+}
+
+/// Best effort parsing of a macro invocation's tokens, either as statements,
+/// like the body of `thread_local! { .. }`, or as comma separated expressions,
+/// like the arguments of `println!(..)` and `vec![..]`.
+fn parse_macro_body(mac: &Macro) -> Option<Vec<Stmt>> {
+    if let Ok(stmts) = Block::parse_within.parse2(mac.tokens.clone()) {
+        return Some(stmts);
+    }
+
+    let exprs = Punctuated::<Expr, Comma>::parse_terminated
+        .parse2(mac.tokens.clone())
+        .ok()?;
+    Some(exprs.into_iter().map(|e| Stmt::Expr(e, None)).collect())
+}
+
+/// Whether `expr` refers to the variable `ident` anywhere, including inside of
+/// closures and macro arguments.
+fn expr_uses(expr: &Expr, ident: &Ident) -> bool {
+    let mut uses = Uses {
+        ident,
+        found: false,
+    };
+    uses.visit_expr(expr);
+    uses.found
+}
+
+struct Uses<'i> {
+    ident: &'i Ident,
+    found: bool,
+}
+
+impl<'ast, 'i> Visit<'ast> for Uses<'i> {
+    fn visit_expr_path(&mut self, i: &'ast ExprPath) {
+        if i.qself.is_none() && i.path.get_ident().map(IdentExt::unraw).as_ref() == Some(self.ident)
+        {
+            self.found = true;
+        }
+    }
+
+    fn visit_macro(&mut self, i: &'ast Macro) {
+        if i.tokens
+            .clone()
+            .into_iter()
+            .any(|t| tokens_use(t, self.ident))
+        {
+            self.found = true;
+        }
+    }
+}
+
+/// `n` followed by `word`, with an `s` unless `n` is 1.
+fn plural(n: usize, word: &str) -> String {
+    format!("{} {}{}", n, word, if n == 1 { "" } else { "s" })
+}
+
+/// The longest snippet of source shown for a statement, in characters.
+const SNIPPET_LENGTH: usize = 60;
+
+/// The variants of the prelude, which patterns can name without a path.
+const PRELUDE_VARIANTS: &[&str] = &["None", "Some", "Ok", "Err"];
+
+/// The source text of `span` on a single line, truncated to `SNIPPET_LENGTH`.
+fn snippet(span: Span) -> Option<String> {
+    Some(shorten(&span.source_text()?))
+}
+
+/// `text` on a single line, truncated to `SNIPPET_LENGTH`.
+fn shorten(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= SNIPPET_LENGTH {
+        return text;
+    }
+    let cut: String = text.chars().take(SNIPPET_LENGTH - 1).collect();
+    cut + "…"
+}
+
+/// The variable that a `for` loop iterates over, when the loop's expression is
+/// the variable itself, a reference to it or one of its iterators, like `items`,
+/// `&items` or `items.iter().enumerate()`.
+fn iterated_ident(expr: &Expr) -> Option<Ident> {
+    match expr {
+        Expr::Reference(r) => iterated_ident(&r.expr),
+        Expr::Paren(p) => iterated_ident(&p.expr),
+        Expr::MethodCall(m)
+            if m.args.is_empty()
+                && ["iter", "iter_mut", "into_iter", "enumerate"]
+                    .contains(&&*m.method.to_string()) =>
+        {
+            iterated_ident(&m.receiver)
+        }
+        _ => expr_ident(expr),
+    }
+}
+
+/// Whether an item is marked as test code, with `#[test]` (or a test attribute
+/// from a crate, like `#[tokio::test]`) or `#[cfg(test)]`.
+fn is_test_attr(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| match &attr.meta {
+        Meta::Path(path) => path.segments.last().is_some_and(|s| s.ident == "test"),
+        // `#[cfg(test)]` and `#[cfg(all(test, ..))]`, but not `#[cfg(not(test))]`.
+        Meta::List(list) if list.path.is_ident("cfg") => {
+            let tokens = list.tokens.to_string();
+            let words: Vec<&str> = tokens
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .collect();
+            words.contains(&"test") && !words.contains(&"not")
+        }
+        _ => false,
+    })
+}
+
+/// The predicates of the `#[cfg(..)]` attributes in `attrs`, as written, like
+/// `windows` or `feature = "serde"`. `#[cfg(test)]` is left out, test code is
+/// labeled as such already.
+fn cfg_predicates(attrs: &[Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::List(list) if list.path.is_ident("cfg") => {
+                let text = list.tokens.span().source_text();
+                Some(text.unwrap_or_else(|| list.tokens.to_string()))
+            }
+            _ => None,
+        })
+        .filter(|predicate| predicate != "test")
+        .collect()
+}
+
+/// Collects the identifiers in a macro's tokens, and the names used by the
+/// format strings among them.
+fn token_names(tree: TokenTree, names: &mut Vec<String>) {
+    match tree {
+        TokenTree::Ident(i) => names.push(i.unraw().to_string()),
+        TokenTree::Group(g) => {
+            for tree in g.stream() {
+                token_names(tree, names);
+            }
+        }
+        TokenTree::Literal(l) => {
+            let literal = l.to_string();
+            if literal.starts_with('"') {
+                // `{{` is an escaped brace, not an argument.
+                for arg in literal.replace("{{", "").split('{').skip(1) {
+                    let arg = arg.strip_prefix("r#").unwrap_or(arg);
+                    let name: String = arg
+                        .chars()
+                        .take_while(|c| c.is_alphanumeric() || *c == '_')
+                        .collect();
+                    if name.starts_with(|c: char| !c.is_ascii_digit()) {
+                        names.push(name);
+                    }
+                }
+            }
+        }
+        TokenTree::Punct(_) => {}
+    }
+}
+
+fn tokens_use(tree: TokenTree, ident: &Ident) -> bool {
+    match tree {
+        TokenTree::Ident(i) => i.unraw() == *ident,
+        TokenTree::Group(g) => g.stream().into_iter().any(|t| tokens_use(t, ident)),
+        _ => false,
+    }
+}
+
+/// Gets the identifier (without any `r#`) of a `let` pattern that binds a
+/// single variable, like `x`, `mut x` or `x: T`.
+fn local_ident(pat: &Pat) -> Option<Ident> {
+    match pat {
+        Pat::Ident(i) if i.by_ref.is_none() && i.subpat.is_none() => Some(i.ident.unraw()),
+        Pat::Type(t) => local_ident(&t.pat),
+        _ => None,
+    }
+}
+
+/// Gets the declared type of a pattern that binds a single variable, like
+/// `u8` in `let x: u8`.
+fn local_type(pat: &Pat) -> Option<String> {
+    match pat {
+        Pat::Type(t) if local_ident(&t.pat).is_some() => type_name(&t.ty),
+        _ => None,
+    }
+}
+
+/// A short name for a type, used to tell whether two bindings have the same
+/// type. Generic arguments are left out, so `Vec<u8>` and `Vec<String>` are
+/// both `Vec`. Types that can't be summarized, like `impl Trait`, are `None`.
+fn type_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(p) if p.qself.is_none() => p.path.segments.last().map(|s| s.ident.to_string()),
+        Type::Reference(r) => {
+            let mutability = if r.mutability.is_some() { "mut " } else { "" };
+            Some(format!("&{}{}", mutability, type_name(&r.elem)?))
+        }
+        Type::Slice(s) => Some(format!("[{}]", type_name(&s.elem)?)),
+        Type::Tuple(t) => {
+            let elems: Option<Vec<String>> = t.elems.iter().map(type_name).collect();
+            Some(format!("({})", elems?.join(", ")))
+        }
+        Type::Paren(p) => type_name(&p.elem),
+        Type::Group(g) => type_name(&g.elem),
+        _ => None,
+    }
+}
+
+/// Guesses the type of an expression from its syntax alone, like `String` for
+/// `String::from("a")` or `x.to_string()`, and `usize` for `x.len()`. Without
+/// type checking this only covers literals, casts, constructors and a few
+/// well known methods and macros. Unsuffixed number literals are
+/// `{integer}` or `{float}`.
+fn expr_type(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(l) => match &l.lit {
+            Lit::Str(_) => Some("&str".into()),
+            Lit::Byte(_) => Some("u8".into()),
+            Lit::Char(_) => Some("char".into()),
+            Lit::Bool(_) => Some("bool".into()),
+            Lit::Int(i) if i.suffix().is_empty() => Some("{integer}".into()),
+            Lit::Int(i) => Some(i.suffix().into()),
+            Lit::Float(f) if f.suffix().is_empty() => Some("{float}".into()),
+            Lit::Float(f) => Some(f.suffix().into()),
+            _ => None,
+        },
+        Expr::Cast(c) => type_name(&c.ty),
+        Expr::Paren(p) => expr_type(&p.expr),
+        Expr::Group(g) => expr_type(&g.expr),
+        Expr::Reference(r) => {
+            let mutability = if r.mutability.is_some() { "mut " } else { "" };
+            Some(format!("&{}{}", mutability, expr_type(&r.expr)?))
+        }
+        Expr::Struct(s) => s.path.segments.last().map(|s| s.ident.to_string()),
+        // `Type::new(..)`, `Type::from(..)`, etc.
+        Expr::Call(c) => match &*c.func {
+            Expr::Path(ExprPath {
+                qself: None, path, ..
+            }) if path.segments.len() >= 2 => {
+                let ctor = &path.segments[path.segments.len() - 1].ident;
+                let ty = &path.segments[path.segments.len() - 2].ident;
+                let is_ctor = ["new", "from", "default", "with_capacity"]
+                    .iter()
+                    .any(|name| ctor == name);
+                let is_type = ty.to_string().starts_with(char::is_uppercase);
+
+                if is_ctor && is_type {
+                    Some(ty.to_string())
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        },
+        Expr::MethodCall(m) => match m.method.to_string().as_str() {
+            "to_string" => Some("String".into()),
+            "len" | "count" => Some("usize".into()),
+            _ => None,
+        },
+        Expr::Macro(m) if m.mac.path.is_ident("format") => Some("String".into()),
+        Expr::Macro(m) if m.mac.path.is_ident("vec") => Some("Vec".into()),
+        Expr::Binary(b) => match b.op {
+            BinOp::Eq(_)
+            | BinOp::Ne(_)
+            | BinOp::Lt(_)
+            | BinOp::Le(_)
+            | BinOp::Gt(_)
+            | BinOp::Ge(_)
+            | BinOp::And(_)
+            | BinOp::Or(_) => Some("bool".into()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether two type names guessed from the syntax look like different types.
+/// An unsuffixed literal matches any type of its kind.
+fn types_differ(a: &str, b: &str) -> bool {
+    const INTEGERS: &[&str] = &[
+        "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+    ];
+    const FLOATS: &[&str] = &["f32", "f64"];
+
+    let matches_literal = |literal: &str, ty: &str| match literal {
+        "{integer}" => INTEGERS.contains(&ty),
+        "{float}" => FLOATS.contains(&ty),
+        _ => false,
+    };
+
+    a != b && !matches_literal(a, b) && !matches_literal(b, a)
+}
+
+/// Gets the identifier (without any `r#`) of an expression that is just a
+/// local variable, like `x`.
+fn expr_ident(expr: &Expr) -> Option<Ident> {
+    match expr {
+        Expr::Path(ExprPath {
+            qself: None, path, ..
+        }) if path.leading_colon.is_none() && path.segments.len() == 1 => {
+            Some(path.segments[0].ident.unraw())
+        }
+        _ => None,
+    }
+}
+
+/// Whether `expr` unwraps the variable `ident`, like `x?`, `x.unwrap()` or
+/// `x.expect("..")`.
+fn is_narrowing(expr: &Expr, ident: &Ident) -> bool {
+    match expr {
+        Expr::Try(t) => expr_ident(&t.expr).as_ref() == Some(ident),
+        Expr::MethodCall(m) if m.method == "unwrap" || m.method == "expect" => {
+            expr_ident(&m.receiver).as_ref() == Some(ident)
+        }
+        _ => false,
+    }
+}
+
+/// Whether `expr` converts the variable `ident` to an owned or borrowed
+/// version of itself, like `x.clone()`, `x.to_owned()`, `x.to_string()` or
+/// `x.as_ref()`.
+fn is_conversion(expr: &Expr, ident: &Ident) -> bool {
+    match expr {
+        Expr::MethodCall(m) if m.args.is_empty() => {
+            let is_conversion = ["clone", "to_owned", "to_string", "as_ref"]
+                .iter()
+                .any(|name| m.method == name);
+            is_conversion && expr_ident(&m.receiver).as_ref() == Some(ident)
+        }
+        _ => false,
+    }
+}
+
+/// How a tracked name is written in the source. Keywords can only be used as
+/// names in their raw form, like `r#type`.
+fn ident_name(ident: &Ident) -> String {
+    let name = ident.to_string();
+    if syn::parse_str::<Ident>(&name).is_ok() {
+        name
+    } else {
+        format!("r#{}", name)
+    }
+}
+
+fn elems_idents<'p>(elems: &'p Punctuated<Pat, Comma>, idents: &mut Vec<&'p PatIdent>) {
+    for p in elems {
+        pat_idents(p, idents);
+    }
+}
+
+impl<'ast, 'a> visit::Visit<'ast> for ShadowCounter<'a> {
+    fn visit_file(&mut self, i: &'ast File) {
+        // Items can be used before they are declared, so they are collected
+        // before any function in the module is visited.
+        self.enums = get_enums(i);
+        self.structs = get_structs(i);
+        self.modules.push(get_module_items(&i.items, &self.enums));
+        visit::visit_file(self, i);
+        self.modules.pop();
+    }
+
+    fn visit_item_mod(&mut self, i: &'ast ItemMod) {
+        let cfgs = self.push_cfgs(&i.attrs);
+        let is_test = is_test_attr(&i.attrs);
+        self.test_modules += is_test as usize;
+        self.mod_path.push(i.ident.to_string());
+        if let Some((_, items)) = &i.content {
+            self.modules.push(get_module_items(items, &self.enums));
+            visit::visit_item_mod(self, i);
+            self.modules.pop();
+        } else if let Some(path) = include::mod_target(i, &self.source_dir()) {
+            // A `#[path]` module's file is usually outside of the module tree,
+            // so it is visited here, as the module it declares.
+            self.visit_included(path, |counter, file| {
+                counter
+                    .modules
+                    .push(get_module_items(&file.items, &counter.enums));
+                file.items.iter().for_each(|item| counter.visit_item(item));
+                counter.modules.pop();
+            });
+        }
+        // Plain `mod m;` declarations are visited when their own file is walked.
+        self.mod_path.pop();
+        self.test_modules -= is_test as usize;
+        self.cfgs.truncate(cfgs);
+    }
+
+    fn visit_item_macro(&mut self, i: &'ast ItemMacro) {
+        match include::macro_target(i, &self.source_dir()) {
+            // The included items are part of the including module.
+            Some(path) => self.visit_included(path, |counter, file| {
+                let items = get_module_items(&file.items, &counter.enums);
+                let module = counter.modules.len() - 1;
+                let before = counter.modules[module].len();
+                counter.modules[module].extend(items);
+                file.items.iter().for_each(|item| counter.visit_item(item));
+                counter.modules[module].truncate(before);
+            }),
+            None if self.options.macro_rules && i.mac.path.is_ident("macro_rules") => {
+                self.scan_macro_rules(i);
+            }
+            None => visit::visit_item_macro(self, i),
+        }
+    }
+
+    fn visit_item_fn(&mut self, i: &'ast ItemFn) {
+        // println!("{}", i.sig.ident.to_string());
+        let cfgs = self.push_cfgs(&i.attrs);
+        self.enter_function(&i.sig, &i.attrs, i.span().end());
+        // self.current_func = i.ident.clone();
+        visit::visit_item_fn(self, i);
+        self.exit_function();
+        self.cfgs.truncate(cfgs);
+    }
+
+    fn visit_item_impl(&mut self, i: &'ast ItemImpl) {
+        let outer = std::mem::replace(&mut self.owner, impl_owner(i));
+        let cfgs = self.push_cfgs(&i.attrs);
+        visit::visit_item_impl(self, i);
+        self.cfgs.truncate(cfgs);
+        self.owner = outer;
+    }
+
+    fn visit_item_trait(&mut self, i: &'ast ItemTrait) {
+        let outer = self.owner.replace((i.ident.to_string(), None));
+        let cfgs = self.push_cfgs(&i.attrs);
+        visit::visit_item_trait(self, i);
+        self.cfgs.truncate(cfgs);
+        self.owner = outer;
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast ImplItemFn) {
+        // println!("{}", i.sig.ident.to_string());
+        let cfgs = self.push_cfgs(&i.attrs);
+        self.enter_function(&i.sig, &i.attrs, i.span().end());
+        self.set_owner(Some(&i.sig));
+        // self.current_func = i.ident.clone();
+        visit::visit_impl_item_fn(self, i);
+        self.exit_function();
+        self.cfgs.truncate(cfgs);
+    }
+
+    fn visit_trait_item_fn(&mut self, i: &'ast TraitItemFn) {
+        // Only provided (default) methods have a body to analyze.
+        if i.default.is_some() {
+            let cfgs = self.push_cfgs(&i.attrs);
+            self.enter_function(&i.sig, &i.attrs, i.span().end());
+            self.set_owner(Some(&i.sig));
+            visit::visit_trait_item_fn(self, i);
+            self.exit_function();
+            self.cfgs.truncate(cfgs);
+        }
+    }
+
+    fn visit_item_const(&mut self, i: &'ast ItemConst) {
+        let cfgs = self.push_cfgs(&i.attrs);
+        self.enter_item("const", &i.ident, &i.attrs, i.span().end());
+        self.visit_body(&i.expr);
+        self.exit_function();
+        self.cfgs.truncate(cfgs);
+    }
+
+    fn visit_item_static(&mut self, i: &'ast ItemStatic) {
+        let cfgs = self.push_cfgs(&i.attrs);
+        self.enter_item("static", &i.ident, &i.attrs, i.span().end());
+        self.visit_body(&i.expr);
+        self.exit_function();
+        self.cfgs.truncate(cfgs);
+    }
+
+    fn visit_impl_item_const(&mut self, i: &'ast ImplItemConst) {
+        let cfgs = self.push_cfgs(&i.attrs);
+        self.enter_item("const", &i.ident, &i.attrs, i.span().end());
+        self.set_owner(None);
+        self.visit_body(&i.expr);
+        self.exit_function();
+        self.cfgs.truncate(cfgs);
+    }
+
+    fn visit_trait_item_const(&mut self, i: &'ast TraitItemConst) {
+        // Only consts with a default value have an initializer to analyze.
+        if let Some((_, expr)) = &i.default {
+            let cfgs = self.push_cfgs(&i.attrs);
+            self.enter_item("const", &i.ident, &i.attrs, i.span().end());
+            self.set_owner(None);
+            self.visit_body(expr);
+            self.exit_function();
+            self.cfgs.truncate(cfgs);
+        }
+    }
+
+    fn visit_expr_closure(&mut self, i: &'ast ExprClosure) {
+        // A closure's arguments and locals are only visible inside of its body,
+        // but they may still shadow the bindings of the enclosing function.
+        self.push_scope(Some("closure"));
+        if let Some(frame) = self.frames.last_mut() {
+            frame.closures.push(frame.scopes.len() - 1);
+        }
+
+        for arg in &i.inputs {
+            let binding = Binding {
+                ty: local_type(arg),
+                span: Some(arg.span()),
+                is_closure_arg: true,
+                source: Some(InitKind::Argument),
+                ..Binding::default()
+            };
+            self.bind(get_idents(arg), binding);
+        }
+
+        for arg in &i.inputs {
+            self.visit_pat(arg);
+        }
+        // The body of an async closure only runs when its future is awaited.
+        let awaits = self.awaits();
+        self.visit_body(&i.body);
+        if i.asyncness.is_some() {
+            self.restore_awaits(awaits);
+        }
+        if let Some(frame) = self.frames.last_mut() {
+            frame.closures.pop();
+        }
+        self.pop_scope();
+    }
+
+    fn visit_expr_match(&mut self, i: &'ast ExprMatch) {
+        self.visit_expr(&i.expr);
+
+        for arm in &i.arms {
+            // Arm bindings are only visible in the arm's guard and body.
+            self.push_scope(Some("match arm"));
+            // `match result { result => .. }` is almost always a mistake for
+            // a pattern, like a misspelled variant or constant.
+            let rebinds_scrutinee = match (&arm.pat, expr_ident(&i.expr)) {
+                (Pat::Ident(pat), Some(scrutinee)) => pat.ident.unraw() == scrutinee,
+                _ => false,
+            };
+            let binding = Binding {
+                span: Some(arm.pat.span()),
+                rebinds_scrutinee,
+                source: Some(InitKind::Pattern),
+                ..Binding::new(Some(&i.expr))
+            };
+            self.bind(self.pattern_bindings(&arm.pat), binding);
+
+            self.visit_pat(&arm.pat);
+            if let Some((_, guard)) = &arm.guard {
+                self.visit_expr(guard);
+            }
+            self.visit_body(&arm.body);
+            self.pop_scope();
+        }
+    }
+
+    fn visit_expr_let(&mut self, i: &'ast ExprLet) {
+        // The matched expression is evaluated before the pattern binds anything.
+        self.visit_expr(&i.expr);
+        let binding = Binding {
+            is_conditional: true,
+            span: Some(i.span()),
+            source: Some(InitKind::Pattern),
+            ..Binding::new(Some(&i.expr))
+        };
+        self.bind(self.pattern_bindings(&i.pat), binding);
+    }
+
+    fn visit_expr_if(&mut self, i: &'ast ExprIf) {
+        // Bindings of an `if let` (or a chain of them joined by `&&`) are only
+        // visible in the `then` branch.
+        self.push_scope(Some("if"));
+        self.visit_expr(&i.cond);
+        self.visit_block(&i.then_branch);
+        self.pop_scope();
+
+        if let Some((_, else_branch)) = &i.else_branch {
+            match &**else_branch {
+                Expr::Block(block) => {
+                    self.push_scope(Some("else"));
+                    self.visit_block(&block.block);
+                    self.pop_scope();
+                }
+                // An `else if`, whose scope is labeled by `visit_expr_if`.
+                else_if => self.visit_expr(else_if),
+            }
+        }
+    }
+
+    fn visit_expr_while(&mut self, i: &'ast ExprWhile) {
+        self.push_scope(Some("while"));
+        self.visit_expr(&i.cond);
+        self.visit_block(&i.body);
+        self.pop_scope();
+    }
+
+    fn visit_expr_for_loop(&mut self, i: &'ast ExprForLoop) {
+        // The iterator is evaluated once, outside of the loop variable's scope.
+        self.visit_expr(&i.expr);
+
+        self.push_scope(Some("for"));
+        let binding = Binding {
+            is_loop: true,
+            iterated: iterated_ident(&i.expr),
+            source: Some(InitKind::LoopVariable),
+            span: Some(i.pat.span()),
+            ..Binding::new(Some(&i.expr))
+        };
+        self.bind(get_idents(&i.pat), binding);
+        self.visit_block(&i.body);
+        self.pop_scope();
+    }
+
+    fn visit_expr_unsafe(&mut self, i: &'ast ExprUnsafe) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.unsafe_blocks += 1;
+        }
+        self.push_scope(Some("unsafe"));
+        visit::visit_expr_unsafe(self, i);
+        self.pop_scope();
+        if let Some(frame) = self.frames.last_mut() {
+            frame.unsafe_blocks -= 1;
+        }
+    }
+
+    fn visit_expr_await(&mut self, i: &'ast ExprAwait) {
+        // The awaited future is evaluated before the task can be suspended.
+        visit::visit_expr_await(self, i);
+        if let Some(frame) = self.frames.last_mut() {
+            frame.awaits += 1;
+        }
+    }
+
+    // The scopes below only label the scope of the block they contain.
+
+    fn visit_expr_block(&mut self, i: &'ast ExprBlock) {
+        let cfgs = self.push_cfgs(&i.attrs);
+        self.push_scope(Some("block"));
+        visit::visit_expr_block(self, i);
+        self.pop_scope();
+        self.cfgs.truncate(cfgs);
+    }
+
+    fn visit_expr_const(&mut self, i: &'ast ExprConst) {
+        self.push_scope(Some("const block"));
+        visit::visit_expr_const(self, i);
+        self.pop_scope();
+    }
+
+    fn visit_expr_loop(&mut self, i: &'ast ExprLoop) {
+        self.push_scope(Some("loop"));
+        visit::visit_expr_loop(self, i);
+        self.pop_scope();
+    }
+
+    fn visit_expr_async(&mut self, i: &'ast ExprAsync) {
+        // The `.await`s of an async block suspend its own future, not the
+        // function it is written in.
+        let awaits = self.awaits();
+        self.push_scope(Some("async block"));
+        visit::visit_expr_async(self, i);
+        self.pop_scope();
+        self.restore_awaits(awaits);
+    }
+
+    fn visit_block(&mut self, i: &'ast Block) {
+        // Locals declared in a block go out of scope at its end, so they can't
+        // shadow (or be shadowed by) bindings in sibling blocks. This includes
+        // `async` blocks, which see the enclosing function's locals like any
+        // other block even though the future may run later.
+        self.push_scope(None);
+        visit::visit_block(self, i);
+        self.pop_scope();
+    }
+
+    fn visit_expr_path(&mut self, i: &'ast ExprPath) {
+        if let (None, Some(ident)) = (&i.qself, i.path.get_ident()) {
+            self.mark_used(&ident.unraw().to_string());
+        }
+        visit::visit_expr_path(self, i);
+    }
+
+    fn visit_macro(&mut self, i: &'ast Macro) {
+        // Any identifier passed to a macro may be a use of a variable, whatever
+        // the macro does with it. This includes the implicit arguments of
+        // format strings, like `x` in `println!("{x}")`.
+        let mut names = Vec::new();
+        for tree in i.tokens.clone() {
+            token_names(tree, &mut names);
+        }
+        for name in &names {
+            self.mark_used(name);
+        }
+
+        if self.options.macros {
+            if let Some(stmts) = parse_macro_body(i) {
+                // Hygiene keeps a macro's locals from leaking into the caller.
+                let in_macro = std::mem::replace(&mut self.in_macro, true);
+                self.push_scope(Some("macro"));
+                for stmt in &stmts {
+                    Visit::visit_stmt(self, stmt);
+                }
+                self.pop_scope();
+                self.in_macro = in_macro;
+            }
+        }
+
+        visit::visit_macro(self, i);
+    }
+
+    fn visit_local(&mut self, i: &'ast Local) {
+        // println!("{:?}", i);
+
+        // The initializer (and the `else` of a `let ... else`) can't see the
+        // new bindings, so `let x = { let x = 5; x };` shadows nothing.
+        visit::visit_local(self, i);
+
+        // `let Some(x) = x else { .. };` narrows like an `if let`.
+        let is_conditional = i.init.as_ref().is_some_and(|init| init.diverge.is_some());
+        // Get the possible identifiers.
+        let ids = if is_conditional {
+            self.pattern_bindings(&i.pat)
+        } else {
+            get_idents(&i.pat)
+        };
+        let init = i.init.as_ref().map(|init| &*init.expr);
+
+        let is_rebind = match (local_ident(&i.pat), init) {
+            (Some(ident), Some(init)) => expr_ident(init) == Some(ident),
+            _ => false,
+        };
+        // A written out type wins over the one guessed from the value.
+        let ty = match local_ident(&i.pat) {
+            Some(_) => local_type(&i.pat).or_else(|| init.and_then(expr_type)),
+            None => None,
+        };
+        let cfgs = self.push_cfgs(&i.attrs);
+        self.bind(
+            ids,
+            Binding {
+                init,
+                is_rebind,
+                is_conditional,
+                source: is_conditional.then_some(InitKind::Pattern),
+                snippet: init.and_then(|init| snippet(i.let_token.span.join(init.span())?)),
+                ty,
+                span: Some(i.span()),
+                ..Binding::default()
+            },
+        );
+        self.cfgs.truncate(cfgs);
+    }
+}
+
+/// Parses a source file, including single file scripts that start with a
+/// frontmatter block, like `---\n[dependencies]\n---`, after the shebang.
+/// The frontmatter isn't Rust, so it is blanked out, keeping the line numbers
+/// of the code after it unchanged. `syn` already skips shebang lines.
+fn parse_source(source: &str) -> syn::Result<File> {
+    let mut lines: Vec<&str> = source.lines().collect();
+
+    let start = match lines.first() {
+        Some(line) if line.starts_with("#!") && !line.starts_with("#![") => 1,
+        _ => 0,
+    };
+    let open = lines[start..]
+        .iter()
+        .position(|line| !line.trim().is_empty())
+        .map(|i| i + start);
+
+    let open = match open {
+        Some(open) => open,
+        None => return syn::parse_file(source),
+    };
+
+    // The block is closed by the same number of dashes that opened it.
+    let dashes = lines[open].chars().take_while(|&c| c == '-').count();
+    let fence = "-".repeat(dashes);
+    let close = lines[open + 1..]
+        .iter()
+        .position(|line| line.trim_end() == fence)
+        .map(|i| i + open + 1);
+
+    if let Some(close) = close.filter(|_| dashes >= 3) {
+        for line in &mut lines[open..=close] {
+            *line = "";
+        }
+        return syn::parse_file(&lines.join("\n"));
+    }
+
+    syn::parse_file(source)
+}
+
+/// The functions of the file `counter` visited, by whether they have
+/// something to report, in the order of the file.
+fn report(counter: ShadowCounter) -> output::FileReport {
+    let (reported, mut clean): (Vec<Function>, Vec<Function>) =
+        counter.funcs.into_iter().partition(|f| f.has_shadow);
+    // Test code is listed after the rest, so it can be weighed separately.
+    let (mut tests, mut funcs): (Vec<Function>, Vec<Function>) =
+        reported.into_iter().partition(|f| f.is_test);
+
+    // Functions are kept in the order they were visited, which is not always
+    // their order in the file, like the doctests that are visited last.
+    for funcs in [&mut funcs, &mut tests, &mut clean] {
+        funcs.sort_by_key(|f| (f.loc, f.col));
+    }
+
+    output::FileReport {
+        file: counter.filename.to_string(),
+        funcs,
+        tests,
+        clean,
+    }
+}
+
+/// Whether the output should be colored, for the value of `--color`. By
+/// default, escape codes are left out of files, pipes and CI logs, and when
+/// the `NO_COLOR` environment variable is set to anything.
+fn use_color(when: &str, to_terminal: bool) -> bool {
+    match when {
+        "always" => true,
+        "never" => false,
+        _ => env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && to_terminal,
+    }
+}
+
+/// Whether the terminal is one known to support OSC 8 hyperlinks. There is no
+/// way to ask, and others print the escape codes as garbage.
+fn supports_hyperlinks() -> bool {
+    let term_program = env::var("TERM_PROGRAM").unwrap_or_default();
+    let vte_version = env::var("VTE_VERSION")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok());
+    ["iTerm.app", "WezTerm", "vscode", "ghostty"].contains(&term_program.as_str())
+        || vte_version.is_some_and(|v| v >= 5000)
+        || env::var_os("WT_SESSION").is_some()
+        || env::var_os("KITTY_WINDOW_ID").is_some()
+}
+
+/// The long help of `--format-template`, with every placeholder.
+fn template_help() -> String {
+    let mut help = String::from(
+        "Write a line per shadow from TEMPLATE instead of the report, like \
+         \"{file}:{line}:{col} {var} shadows {orig_line}\". {{ and }} are literal \
+         braces. The placeholders are:\n",
+    );
+    for (name, description) in output::template::PLACEHOLDERS {
+        help += &format!("\n    {:<16} {}", format!("{{{}}}", name), description);
+    }
+    help
+}
+
+/// A buffered writer to a new file at `path`, for a report.
+fn create(path: &str) -> Box<dyn io::Write> {
+    match fs::File::create(path) {
+        Ok(file) => Box::new(io::BufWriter::new(file)),
+        Err(e) => {
+            eprintln!("{}: {}: {}", "Unable to create".red(), path, e);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Hands the report of a file to every output.
+fn write_report(outputs: &mut [output::Output], report: output::FileReport) {
+    if let Some((last, others)) = outputs.split_last_mut() {
+        for out in others {
+            out.file(report.clone());
+        }
+        last.file(report);
+    }
+}
+
+/// The command line arguments, starting with the `light` subcommand. Cargo
+/// passes it when run as `cargo light`, but not when `cargo-light` is run
+/// directly. `--version` is left to the app, which it belongs to.
+fn args() -> Vec<String> {
+    let mut args: Vec<String> = env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("light") | Some("-V") | Some("--version") => {}
+        _ => args.insert(1.min(args.len()), "light".into()),
+    }
+    args
+}
+
+fn main() {
+    // println!("{}", Startom)
+    let matches = App::new("cargo-light")
+        .about("Finds and prints potential usages of shadowed variables.")
+        .author("Fisher Darling <fdarlingco@gmail.com>")
+        .version(env!("CARGO_PKG_VERSION"))
+        .bin_name("cargo")
+        .subcommand(
+            SubCommand::with_name("light")
+                .version(env!("CARGO_PKG_VERSION"))
+                .arg(
+                    Arg::with_name("files")
+                        .short("F")
+                        .long("files")
+                        .takes_value(true)
+                        .multiple(true)
+                        .help("Files to be parsed (can accept a glob)."),
+                )
+                .arg(
+                    Arg::with_name("dir")
+                        .short("d")
+                        .long("directory")
+                        .takes_value(true)
+                        .multiple(false)
+                        .help("Directory to walk and parse."),
+                )
+                .arg(Arg::with_name("strict").long("strict").help(
+                    "Also report narrowing, like `let x = x?;`, `let x = x.unwrap();` \
+                             and `if let Some(x) = x`.",
+                ))
+                // Narrowing is allowed by default now, the flag is only kept so
+                // existing invocations keep working.
+                .arg(
+                    Arg::with_name("allow-narrowing")
+                        .long("allow-narrowing")
+                        .hidden(true)
+                        .conflicts_with("strict"),
+                )
+                .arg(
+                    Arg::with_name("include-doctests")
+                        .long("include-doctests")
+                        .help("Also analyze the Rust code blocks in doc comments."),
+                )
+                .arg(
+                    Arg::with_name("macros")
+                        .long("macros")
+                        .help("Also analyze code inside macro invocations (heuristic)."),
+                )
+                .arg(Arg::with_name("macro-rules").long("macro-rules").help(
+                    "Also report names bound more than once in an arm of a \
+                             `macro_rules!` definition (heuristic).",
+                ))
+                .arg(
+                    Arg::with_name("package")
+                        .short("p")
+                        .long("package")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("SPEC")
+                        .help("Only analyze the given packages of the workspace."),
+                )
+                .arg(
+                    Arg::with_name("workspace")
+                        .long("workspace")
+                        .help("Analyze every package of the workspace."),
+                )
+                .arg(
+                    Arg::with_name("exclude")
+                        .long("exclude")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("SPEC")
+                        .help(
+                            "With --workspace, leave out the given packages of the workspace. \
+                             Without it, leave out the files and directories matched by the glob \
+                             SPEC, like --exclude-path.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("exclude-path")
+                        .long("exclude-path")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("PATTERN")
+                        .help(
+                            "Leave out the files and directories matched by the glob PATTERN, \
+                             like src/generated or '*_snapshot.rs', with or without --workspace.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("expand")
+                        .long("expand")
+                        .conflicts_with_all(&["emit", "format-template"])
+                        .help(
+                            "Analyze the macro expanded source of every target, using `cargo \
+                             expand`. Only the human readable report can be written, since \
+                             lines refer to the expanded source.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("class")
+                        .long("class")
+                        .takes_value(true)
+                        .possible_values(&["derived", "unrelated", "conversion"])
+                        .help(
+                            "Only report shadows whose value is derived from the shadowed \
+                             variable (`let x = x + 1`), unrelated to it, or an ownership \
+                             conversion of it (`let x = x.clone()`).",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("min-depth")
+                        .long("min-depth")
+                        .takes_value(true)
+                        .value_name("N")
+                        .validator(|n| n.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+                        .help(
+                            "Only report variables that are shadowed at least N times in a \
+                             function (default: 1).",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("min-distance")
+                        .long("min-distance")
+                        .takes_value(true)
+                        .value_name("N")
+                        .validator(|n| n.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+                        .help(
+                            "Only report shadows at least N lines after the end of the \
+                             statement they shadow.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("min-density")
+                        .long("min-density")
+                        .takes_value(true)
+                        .value_name("N")
+                        .validator(|n| n.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+                        .help("Only report functions with at least N shadows per 100 lines."),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .value_name("PATH")
+                        .help("Write the report to PATH instead of stdout."),
+                )
+                .arg(
+                    Arg::with_name("emit")
+                        .long("emit")
+                        .takes_value(true)
+                        .multiple(true)
+                        .value_name("FORMAT=PATH")
+                        .validator(|emit| match emit.split_once('=') {
+                            Some((format, path)) if !path.is_empty() => {
+                                format.parse::<output::Format>().map(|_| ())
+                            }
+                            _ => Err("expected FORMAT=PATH, like json=report.json".into()),
+                        })
+                        .help(
+                            "Also write the report in FORMAT to PATH, like json=report.json. \
+                             Can be given more than once.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("format-template")
+                        .long("format-template")
+                        .takes_value(true)
+                        .value_name("TEMPLATE")
+                        .help(
+                            "Write a line per shadow from TEMPLATE instead, like \
+                             \"{file}:{line}:{col} {var} shadows {orig_line}\".",
+                        )
+                        .long_help(&template_help()),
+                )
+                .arg(
+                    Arg::with_name("sort")
+                        .long("sort")
+                        .takes_value(true)
+                        .possible_values(output::Sort::NAMES)
+                        .default_value("line")
+                        .help(
+                            "The order to list files and their functions in: as analyzed and by \
+                             line, by file name, with the most shadows or the highest shadow \
+                             density first, or by function name.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("top")
+                        .long("top")
+                        .takes_value(true)
+                        .value_name("N")
+                        .validator(|n| n.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+                        .help(
+                            "Only list the N functions with the most shadows of the whole run, \
+                             or the highest shadow density with --sort density.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("group-by")
+                        .long("group-by")
+                        .takes_value(true)
+                        .possible_values(output::GroupBy::NAMES)
+                        .default_value("file")
+                        .help(
+                            "List the files by the module or crate (package) they are in, with \
+                             the number of shadows of each.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(output::Format::NAMES)
+                        .default_value("human")
+                        .help(
+                            "How to write the report: human readable, as a JSON document, as \
+                             JSON Lines with one shadow per line, as a SARIF log, for GitHub \
+                             Actions, Azure Pipelines or GitLab Code Quality, as JUnit XML, as a \
+                             table of comma or tab separated values, as one compiler style line \
+                             per shadow, as a quickfix list for editors, as Markdown for pull \
+                             request comments, as one plain line per shadow for grep, as a \
+                             shields.io badge of the shadow count, or as TeamCity service \
+                             messages.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("quiet")
+                        .short("q")
+                        .long("quiet")
+                        .multiple(true)
+                        .help(
+                            "Only print a line with the totals (twice: nothing at all), and exit \
+                             with 1 if there are shadows.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("verbose")
+                        .short("v")
+                        .long("verbose")
+                        .conflicts_with("quiet")
+                        .help("Also list the functions without shadows, and files with none."),
+                )
+                .arg(
+                    Arg::with_name("color")
+                        .long("color")
+                        .takes_value(true)
+                        .possible_values(&["auto", "always", "never"])
+                        .default_value("auto")
+                        .help(
+                            "When to color the output: only when writing to a terminal and \
+                             NO_COLOR isn't set, always or never.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("hyperlinks")
+                        .long("hyperlinks")
+                        .takes_value(true)
+                        .possible_values(&["auto", "always", "never"])
+                        .default_value("auto")
+                        .help(
+                            "When to make the locations clickable links: only in terminals \
+                             known to support them, always or never.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("link-template")
+                        .long("link-template")
+                        .takes_value(true)
+                        .value_name("URL")
+                        .help(
+                            "The URL that locations link to, with {path}, {line} and {col} \
+                             replaced, like vscode://file{path}:{line}:{col}. Defaults to \
+                             file://{path}.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("absolute-paths")
+                        .long("absolute-paths")
+                        .help(
+                        "Show the absolute paths of files, instead of relative to the workspace.",
+                    ),
+                )
+                .arg(
+                    Arg::with_name("min-severity")
+                        .long("min-severity")
+                        .takes_value(true)
+                        .possible_values(output::Severity::NAMES)
+                        .default_value("info")
+                        .help(
+                            "Only report shadows at least this severe: info for all of them, \
+                             warning for unrelated shadows, hidden items and apparent type \
+                             changes, error for the ones in unsafe code or across an .await.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("baseline")
+                        .long("baseline")
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .help("Only report the shadows that aren't in the baseline FILE."),
+                )
+                .arg(
+                    Arg::with_name("save-baseline")
+                        .long("save-baseline")
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .help(
+                            "Save the fingerprints of every shadow found to FILE, to pass to \
+                             --baseline later.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("histogram")
+                        .long("histogram")
+                        .help("Show how many functions have how many shadows after the summary."),
+                )
+                .arg(
+                    Arg::with_name("max-name-width")
+                        .long("max-name-width")
+                        .takes_value(true)
+                        .value_name("N")
+                        .validator(|n| n.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+                        .help(
+                            "Cut off variable names longer than N characters (default: a \
+                             quarter of the terminal's width).",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("no-color-markers")
+                        .long("no-color-markers")
+                        .help(
+                            "Mark originals with * and shadows with → (like *3:9 →7:13), so they \
+                     can be told apart without colors.",
+                        ),
+                )
+                .arg(Arg::with_name("show-source").long("show-source").help(
+                    "Show the source lines of each shadowed variable, with its bindings \
+                     underlined.",
+                ))
+                .arg(
+                    Arg::with_name("report-rebinds")
+                        .long("report-rebinds")
+                        .help(
+                            "Also report `let x = x;`, which is usually used to move `x` or \
+                             change its mutability.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("include-ref-patterns")
+                        .long("include-ref-patterns")
+                        .help("Also report `ref` and `ref mut` bindings, like `let ref x = y;`."),
+                )
+                .arg(Arg::with_name("sibling-reuse").long("sibling-reuse").help(
+                    "Also report names that are reused in sibling scopes, like two \
+                             blocks that both bind `result`.",
+                ))
+                .arg(
+                    Arg::with_name("check-items")
+                        .long("check-items")
+                        .help("Also report locals that shadow `const` and `static` items."),
+                )
+                .arg(
+                    Arg::with_name("check-fields")
+                        .long("check-fields")
+                        .help("Also report locals in methods that shadow a field of `self`."),
+                )
+                .arg(
+                    Arg::with_name("ignore-underscore")
+                        .long("ignore-underscore")
+                        .help("Ignore bindings whose name starts with `_`, like `_guard`."),
+                )
+                .arg(
+                    Arg::with_name("skip-tests")
+                        .long("skip-tests")
+                        .visible_alias("no-tests")
+                        .conflicts_with("only-tests")
+                        .help("Ignore `#[test]` functions and `#[cfg(test)]` modules."),
+                )
+                .arg(
+                    Arg::with_name("only-tests")
+                        .long("only-tests")
+                        .visible_alias("tests-only")
+                        .help("Only check `#[test]` functions and `#[cfg(test)]` modules."),
+                )
+                .arg(
+                    Arg::with_name("config")
+                        .long("config")
+                        .takes_value(true)
+                        .help(
+                            "Config file to read, instead of looking for cargo-light.toml or \
+                             .cargo-light.toml in the directory.",
+                        ),
+                ),
+        )
+        .get_matches_from(args());
+
+    let light = matches.subcommand_matches("light").unwrap();
+    // The report is only shown in the terminal when it isn't written to a file.
+    let to_terminal = !light.is_present("output") && io::stdout().is_terminal();
+    colored::control::set_override(use_color(light.value_of("color").unwrap(), to_terminal));
+    let config = config::load(
+        light.value_of("config"),
+        light.value_of("dir").unwrap_or("."),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("{}: {}", "Unable to load config".red(), e);
+        std::process::exit(2);
+    });
+    theme::set(Theme::from_table(&config.theme).unwrap_or_else(|e| {
+        eprintln!("{}: {}", "Invalid theme".red(), e);
+        std::process::exit(2);
+    }));
+    let options = Options {
+        strict: light.is_present("strict"),
+        check_items: light.is_present("check-items"),
+        check_fields: light.is_present("check-fields"),
+        macros: light.is_present("macros"),
+        macro_rules: light.is_present("macro-rules"),
+        class: light.value_of("class").map(|c| c.parse().unwrap()),
+        ignore_conversions: config.ignore_conversions,
+        report_rebinds: light.is_present("report-rebinds"),
+        ignore_underscore: light.is_present("ignore-underscore") || config.ignore_underscore,
+        skip_tests: light.is_present("skip-tests"),
+        only_tests: light.is_present("only-tests"),
+        min_depth: light
+            .value_of("min-depth")
+            .map(|n| n.parse().unwrap())
+            .or(config.min_depth)
+            .unwrap_or(1),
+        min_distance: light
+            .value_of("min-distance")
+            .map(|n| n.parse().unwrap())
+            .or(config.min_distance)
+            .unwrap_or(0),
+        min_density: light
+            .value_of("min-density")
+            .map(|n| n.parse().unwrap())
+            .or(config.min_density)
+            .unwrap_or(0.0),
+        include_ref_patterns: light.is_present("include-ref-patterns"),
+        sibling_reuse: light.is_present("sibling-reuse"),
+        include_doctests: light.is_present("include-doctests"),
+    };
+
+    let group_by: output::GroupBy = light.value_of("group-by").unwrap().parse().unwrap();
+    // Modules and crates are told by the targets of the workspace.
+    let targets = match group_by {
+        output::GroupBy::File => Vec::new(),
+        _ => workspace::targets(light.value_of("dir").unwrap_or(".")).unwrap_or_else(|e| {
+            eprintln!("{}: {}", "Unable to read the workspace".red(), e);
+            Vec::new()
+        }),
+    };
+    let baseline = match light.value_of("baseline") {
+        Some(path) => output::baseline::load(path).unwrap_or_else(|e| {
+            eprintln!("{}: {}", "Unable to read the baseline".red(), e);
+            std::process::exit(2);
+        }),
+        None => HashSet::new(),
+    };
+    let template = light.value_of("format-template").map(String::from);
+    if let Some(Err(e)) = template.as_deref().map(output::template::check) {
+        eprintln!("{}: {}", "Invalid template".red(), e);
+        std::process::exit(2);
+    }
+    // Only the functions of expanded code can be pointed back to the files
+    // they are in, so the locations of its shadows mean nothing to tools.
+    if light.is_present("expand") && light.value_of("format") != Some("human") {
+        eprintln!(
+            "{}: --expand only writes the human readable report",
+            "Invalid format".red()
+        );
+        std::process::exit(2);
+    }
+    let settings = output::Settings {
+        format: match template {
+            Some(_) => output::Format::Template,
+            None => light.value_of("format").unwrap().parse().unwrap(),
+        },
+        template,
+        sort: light.value_of("sort").unwrap().parse().unwrap(),
+        group_by,
+        top: light.value_of("top").map(|n| n.parse().unwrap()),
+        baseline,
+        save_baseline: light.value_of("save-baseline").map(String::from),
+        min_severity: light.value_of("min-severity").unwrap().parse().unwrap(),
+        targets,
+        show_source: light.is_present("show-source"),
+        layout: {
+            let width = if light.is_present("output") {
+                None
+            } else {
+                terminal_width()
+            };
+            Layout {
+                // In a terminal, names take up to a quarter of a line. Names
+                // written to files and pipes are never cut off.
+                max_name_width: match light.value_of("max-name-width") {
+                    Some(n) => n.parse().unwrap(),
+                    None => width.map_or(usize::MAX, |width| (width / 4).max(15)),
+                },
+                width,
+                markers: light.is_present("no-color-markers"),
+            }
+        },
+        histogram: light.is_present("histogram"),
+        // Outside of a workspace, paths are shown relative to where cargo
+        // light is run.
+        root: workspace::root(light.value_of("dir").unwrap_or("."))
+            .or_else(|| env::current_dir().ok()?.canonicalize().ok())
+            .unwrap_or_default(),
+        absolute_paths: light.is_present("absolute-paths"),
+        links: match light.value_of("hyperlinks").unwrap() {
+            "always" => true,
+            "never" => false,
+            _ => to_terminal && supports_hyperlinks(),
+        }
+        .then(|| {
+            light
+                .value_of("link-template")
+                .map(String::from)
+                .or(config.link_template.clone())
+                .unwrap_or_else(|| "file://{path}".into())
+        }),
+        verbose: light.is_present("verbose"),
+        quiet: light.occurrences_of("quiet"),
+        summary: true,
+    };
+
+    // Every format is written by an output of its own, the one of `--format`
+    // first. Only that one has a summary, and it is quiet with `--quiet`.
+    let mut outputs = vec![output::Output::new(
+        settings.clone(),
+        match light.value_of("output") {
+            Some(path) => create(path),
+            None => Box::new(io::stdout()),
+        },
+    )];
+    for emit in light.values_of("emit").into_iter().flatten() {
+        let (format, path) = emit.split_once('=').unwrap();
+        let settings = output::Settings {
+            format: format.parse().unwrap(),
+            template: None,
+            save_baseline: None,
+            layout: Layout {
+                max_name_width: usize::MAX,
+                width: None,
+                ..settings.layout
+            },
+            links: None,
+            quiet: 0,
+            summary: false,
+            ..settings.clone()
+        };
+        outputs.push(output::Output::new(settings, create(path)));
+    }
+
+    // With -p or --workspace, only the packages chosen are analyzed.
+    let dir = light.value_of("dir").unwrap_or(".");
+    let selects_packages = light.is_present("package") || light.is_present("workspace");
+    let packages = if selects_packages {
+        workspace::packages(dir).unwrap_or_else(|e| {
+            eprintln!("{}: {}", "Unable to read the workspace".red(), e);
+            std::process::exit(2);
+        })
+    } else {
+        Vec::new()
+    };
+    let names: Vec<&str> = light.values_of("package").into_iter().flatten().collect();
+    // `--exclude` leaves out packages of the workspace with `--workspace`, like
+    // it does for cargo, and paths without it.
+    let (excluded, excluded_paths): (Vec<&str>, Vec<&str>) = {
+        let exclude = light.values_of("exclude").into_iter().flatten();
+        let paths = light.values_of("exclude-path").into_iter().flatten();
+        if light.is_present("workspace") {
+            (exclude.collect(), paths.collect())
+        } else {
+            (Vec::new(), exclude.chain(paths).collect())
+        }
+    };
+    let selected = workspace::select(&packages, &names, light.is_present("workspace"), &excluded)
+        .unwrap_or_else(|e| {
+            eprintln!("{}: {}", "Unable to select the packages".red(), e);
+            std::process::exit(2);
+        });
+
+    if light.is_present("expand") {
+        let selected_names: Vec<&str> = selected.iter().map(|p| p.name.as_str()).collect();
+        let packages = Some(&selected_names[..]).filter(|_| selects_packages);
+        let targets = expand::expand_targets(dir, packages).unwrap_or_else(|e| {
+            eprintln!("{}: {}", "Unable to expand".red(), e);
+            std::process::exit(2);
+        });
+
+        for target in targets {
+            // The other targets are still analyzed when one can't be expanded.
+            let target = match target {
+                Ok(target) => target,
+                Err(e) => {
+                    eprintln!("{}: {}\n", "Unable to expand".red(), e);
+                    continue;
+                }
+            };
+            let syntax = syn::parse_file(&target.source);
+
+            if syntax.is_err() {
+                eprintln!("{}: {}\n", "Unable to parse".red(), target.name);
+                continue;
+            }
+
+            let syntax = syntax.unwrap();
+            let mut visitor = ShadowCounter::new(&target.name, &options);
+            visitor.visit_file(&syntax);
+
+            // Line numbers refer to the expanded source, so point each function
+            // back to its definition where it can be found. Functions with the
+            // same name are told apart by their order.
+            let origins = expand::original_locations(&target.src_path);
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for f in &visitor.funcs {
+                *counts.entry(f.full_name()).or_default() += 1;
+            }
+            let mut seen: HashMap<String, usize> = HashMap::new();
+            for f in &mut visitor.funcs {
+                let name = f.full_name();
+                let nth = seen.entry(name.clone()).or_default();
+                f.origin = expand::original_location(&origins, &name, *nth, counts[&name]);
+                *nth += 1;
+            }
+
+            write_report(&mut outputs, report(visitor));
+        }
+    } else if let Some(files) = light.values_of("files") {
+        for file in files {
+            let source = fs::read_to_string(file).unwrap();
+            let syntax = parse_source(&source).expect("Unable to parse file");
+
+            let mut visitor = ShadowCounter::new(file, &options);
+
+            visitor.visit_file(&syntax);
+            if options.include_doctests {
+                visitor.visit_doctests(&syntax);
+            }
+            write_report(&mut outputs, report(visitor));
+        }
+    } else {
+        let paths = if selects_packages {
+            // A package's directory can have other packages in it, whose
+            // files are only analyzed as theirs.
+            let all = &packages;
+            selected
+                .iter()
+                .flat_map(|package| {
+                    rust_files(&package.dir, &excluded_paths)
+                        .into_iter()
+                        .filter(move |file| {
+                            workspace::package_of(all, Path::new(file))
+                                .is_some_and(|owner| std::ptr::eq(owner, *package))
+                        })
+                })
+                .collect()
+        } else {
+            rust_files(Path::new(dir), &excluded_paths)
+        };
+
+        let mut files = Vec::new();
+        for file in paths {
+            let source = fs::read_to_string(&file).unwrap();
+            let syntax = parse_source(&source);
+
+            if syntax.is_err() {
+                eprintln!("{}: {}\n", "Unable to parse".red(), file);
+                continue;
+            }
+
+            files.push((file, syntax.unwrap()));
+        }
+
+        // Files pulled into another one are analyzed as part of it, and not
+        // again on their own.
+        let included: Vec<PathBuf> = files
+            .iter()
+            .flat_map(|(file, syntax)| include::included_files(syntax, Path::new(file)))
+            .collect();
+
+        for (file, syntax) in &files {
+            let is_included = Path::new(file)
+                .canonicalize()
+                .is_ok_and(|path| included.contains(&path));
+            if is_included {
+                continue;
+            }
+
+            let mut visitor = ShadowCounter::new(file, &options);
+            visitor.visit_file(syntax);
+            if options.include_doctests {
+                visitor.visit_doctests(syntax);
+            }
+
+            write_report(&mut outputs, report(visitor));
+        }
+    }
+
+    // Scripts that only need to know whether there are shadows can go by the
+    // exit code.
+    let mut found = false;
+    for out in outputs {
+        found |= out.finish();
+    }
+    if found && light.is_present("quiet") {
+        std::process::exit(1);
+    }
+}
+
+/// Every .rs file below `dir`, but the ones matched by a pattern of
+/// `excluded` or in a directory that is. Files are walked in order of their
+/// names, so the report is the same on every file system.
+fn rust_files(dir: &Path, excluded: &[&str]) -> Vec<String> {
+    let walker = WalkDir::new(dir)
+        .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+        .into_iter()
+        .filter_entry(|entry| {
+            let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+            !excluded
+                .iter()
+                .any(|pattern| glob::matches_path(pattern, relative))
+        });
+    let mut files = Vec::new();
+
+    for file in walker {
+        let file = file.expect("Unable to parse file name.");
+
+        if !is_file_with_ext(&file, "rs") {
+            // Not a .rs file
+            continue;
+        }
+
+        match file.path().to_str() {
+            Some(file) => files.push(file.to_string()),
+            None => eprintln!("Unable to parse a file."),
         }
     }
+    files
 }
 
 // Taken from cargo-geiger
@@ -0,0 +1,1728 @@
+//! The formats a report can be written in: human readable for the terminal,
+//! JSON for scripts and CI pipelines, or the formats of other tools.
+
+pub mod baseline;
+mod gitlab;
+mod junit;
+mod markdown;
+mod sarif;
+mod source;
+mod teamcity;
+pub mod template;
+
+use colored::Colorize;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use super::{
+    aligned_location, ident_name, plural, Case, Function, ItemKind, Layout, Note, ShadowClass,
+};
+use theme::{self, Element};
+use workspace::{self, Target};
+
+/// How the report is written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Human,
+    /// A single JSON document, written once every file is analyzed.
+    Json,
+    /// One JSON object per finding and line, written as soon as its file is
+    /// analyzed.
+    Jsonl,
+    /// A SARIF 2.1.0 log, for code scanning platforms.
+    Sarif,
+    /// Workflow commands that annotate the changed lines of a pull request
+    /// when run in GitHub Actions.
+    Github,
+    /// A GitLab Code Quality report, shown in the merge request diff.
+    Gitlab,
+    /// JUnit XML, for CI systems that only show test results.
+    Junit,
+    /// A table with a row per finding, with comma or tab separated values.
+    Csv,
+    Tsv,
+    /// A line per finding like the ones of rustc, `file:line:col: warning[..]: ..`,
+    /// which editors and `errorformat`s understand.
+    Short,
+    /// A Markdown summary, to post as a comment on a pull request.
+    Markdown,
+    /// A line per finding with its function and variable, and nothing else,
+    /// for `grep` and `wc -l`.
+    Oneline,
+    /// A line per finding written from the template of `--format-template`.
+    Template,
+    /// The shadow count as a shields.io endpoint, to publish a badge from CI.
+    Badge,
+    /// Service messages that list every finding in the Inspections tab of a
+    /// TeamCity build.
+    Teamcity,
+    /// Logging commands that annotate the run of an Azure Pipelines job.
+    Azure,
+    /// `file:line:col: message` lines in the order of the files, for the
+    /// quickfix list of Vim and the compilation mode of Emacs.
+    Quickfix,
+}
+
+impl Format {
+    /// The names the formats are chosen by with `--format`.
+    pub const NAMES: &'static [&'static str] = &[
+        "human", "json", "jsonl", "sarif", "github", "gitlab", "junit", "csv", "tsv", "short",
+        "markdown", "oneline", "badge", "teamcity", "azure", "quickfix",
+    ];
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Format::Human),
+            "json" => Ok(Format::Json),
+            "jsonl" => Ok(Format::Jsonl),
+            "sarif" => Ok(Format::Sarif),
+            "github" => Ok(Format::Github),
+            "gitlab" => Ok(Format::Gitlab),
+            "junit" => Ok(Format::Junit),
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            "short" => Ok(Format::Short),
+            "markdown" => Ok(Format::Markdown),
+            "oneline" => Ok(Format::Oneline),
+            "badge" => Ok(Format::Badge),
+            "teamcity" => Ok(Format::Teamcity),
+            "azure" => Ok(Format::Azure),
+            "quickfix" => Ok(Format::Quickfix),
+            _ => Err(format!("unknown format: {}", s)),
+        }
+    }
+}
+
+/// The order files and functions are listed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sort {
+    /// Files in the order they are analyzed, and functions by line.
+    Line,
+    /// Files by name, and functions by line.
+    File,
+    /// The most shadows first.
+    Count,
+    /// The highest shadow density first.
+    Density,
+    /// Functions by name, and files by name.
+    Name,
+}
+
+impl Sort {
+    /// The names the orders are chosen by with `--sort`.
+    pub const NAMES: &'static [&'static str] = &["line", "file", "count", "density", "name"];
+
+    /// Sorts the functions of a file, which are given by line.
+    fn functions(self, funcs: &mut [Function]) {
+        match self {
+            Sort::Line | Sort::File => {}
+            Sort::Count => funcs.sort_by_key(|f| std::cmp::Reverse(f.shadows())),
+            Sort::Density => funcs.sort_by(|a, b| b.density().total_cmp(&a.density())),
+            Sort::Name => funcs.sort_by_key(Function::full_name),
+        }
+    }
+
+    /// Sorts the reports of files, which are given in the order they were
+    /// analyzed and have their functions sorted already.
+    fn files(self, reports: &mut [FileReport]) {
+        match self {
+            Sort::Line => {}
+            Sort::File | Sort::Name => reports.sort_by(|a, b| a.file.cmp(&b.file)),
+            Sort::Count => reports.sort_by_key(|report| {
+                std::cmp::Reverse(report.functions().map(Function::shadows).sum::<usize>())
+            }),
+            Sort::Density => {
+                let densest = |report: &FileReport| {
+                    report
+                        .functions()
+                        .map(Function::density)
+                        .fold(0.0, f64::max)
+                };
+                reports.sort_by(|a, b| densest(b).total_cmp(&densest(a)))
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Sort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "line" => Ok(Sort::Line),
+            "file" => Ok(Sort::File),
+            "count" => Ok(Sort::Count),
+            "density" => Ok(Sort::Density),
+            "name" => Ok(Sort::Name),
+            _ => Err(format!("unknown order: {}", s)),
+        }
+    }
+}
+
+/// What the files of the report are listed by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupBy {
+    File,
+    /// The module of each function, like `cargo_light::output`.
+    Module,
+    /// The package each file belongs to.
+    Crate,
+}
+
+impl GroupBy {
+    /// The names the groupings are chosen by with `--group-by`.
+    pub const NAMES: &'static [&'static str] = &["file", "module", "crate"];
+}
+
+impl std::str::FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "file" => Ok(GroupBy::File),
+            "module" => Ok(GroupBy::Module),
+            "crate" => Ok(GroupBy::Crate),
+            _ => Err(format!("unknown grouping: {}", s)),
+        }
+    }
+}
+
+/// How the report is written.
+#[derive(Clone)]
+pub struct Settings {
+    pub format: Format,
+    pub sort: Sort,
+    pub group_by: GroupBy,
+    /// The template of `Format::Template`.
+    pub template: Option<String>,
+    /// Only list this many of the worst functions.
+    pub top: Option<usize>,
+    /// The fingerprints of the shadows that are already known, and left out.
+    pub baseline: HashSet<String>,
+    /// Where to save the fingerprints of every shadow found, if anywhere.
+    pub save_baseline: Option<String>,
+    /// Leave out the findings that are less severe.
+    pub min_severity: Severity,
+    /// The targets of the workspace, which files are grouped by.
+    pub targets: Vec<Target>,
+    /// Show the source lines of the findings, in the human readable format.
+    pub show_source: bool,
+    /// Show the histogram of shadows per function after the summary.
+    pub histogram: bool,
+    /// How wide the columns of the human readable format are.
+    pub layout: Layout,
+    /// The root of the workspace, which paths are shown relative to.
+    pub root: PathBuf,
+    /// Show the absolute paths of files instead.
+    pub absolute_paths: bool,
+    /// The URL template that locations link to in the human readable format,
+    /// if they are hyperlinks.
+    pub links: Option<String>,
+    /// Also list the functions without shadows, in the human readable format.
+    pub verbose: bool,
+    /// Only write a line with the totals (1), or nothing at all (2 or more),
+    /// instead of the report.
+    pub quiet: u64,
+    /// Write the summary of the run after the report.
+    pub summary: bool,
+}
+
+/// The functions of a file that have something to report, tests last, and
+/// the ones that don't.
+#[derive(Clone)]
+pub struct FileReport {
+    pub file: String,
+    pub funcs: Vec<Function>,
+    pub tests: Vec<Function>,
+    pub clean: Vec<Function>,
+}
+
+impl FileReport {
+    fn is_empty(&self) -> bool {
+        self.funcs.is_empty() && self.tests.is_empty()
+    }
+
+    /// The functions of the file, tests last.
+    fn functions(&self) -> impl Iterator<Item = &Function> {
+        self.funcs.iter().chain(&self.tests)
+    }
+}
+
+/// Totals over every analyzed file, for the summary at the end of the run.
+#[derive(Default)]
+struct Stats {
+    files: usize,
+    files_with_shadows: usize,
+    functions: usize,
+    // How many distinct variables are shadowed in each function, summed.
+    variables: usize,
+    shadows: usize,
+    // How many shadows there are of each name.
+    names: HashMap<String, usize>,
+    // How many functions have as many shadows as each of `BUCKETS`.
+    buckets: [usize; 5],
+}
+
+/// The ranges of shadows per function the histogram counts functions in.
+const BUCKETS: [(&str, usize, usize); 5] = [
+    ("0", 0, 0),
+    ("1", 1, 1),
+    ("2", 2, 2),
+    ("3–5", 3, 5),
+    ("6+", 6, usize::MAX),
+];
+
+impl Stats {
+    fn add(&mut self, report: &FileReport) {
+        self.files += 1;
+        // Nothing is reported in clean functions, whatever they bind.
+        let counts = report.functions().map(Function::shadows);
+        for n in counts.chain(report.clean.iter().map(|_| 0)) {
+            let bucket = BUCKETS
+                .iter()
+                .position(|&(_, min, max)| min <= n && n <= max);
+            self.buckets[bucket.unwrap()] += 1;
+        }
+        if report.is_empty() {
+            return;
+        }
+        self.files_with_shadows += 1;
+        self.functions += report.funcs.len() + report.tests.len();
+        self.variables += report
+            .functions()
+            .map(Function::shadowed_variables)
+            .sum::<usize>();
+        for finding in findings(report) {
+            self.shadows += 1;
+            *self.names.entry(finding.variable).or_insert(0) += 1;
+        }
+    }
+
+    /// The totals on a single line, for `--quiet`.
+    fn line(&self) -> String {
+        format!(
+            "cargo light: {} of {} in {} of {} ({} scanned)",
+            plural(self.shadows, "shadow"),
+            plural(self.variables, "variable"),
+            plural(self.functions, "function"),
+            plural(self.files_with_shadows, "file"),
+            self.files
+        )
+    }
+
+    /// A bar for each of `BUCKETS`, as long as the share of functions in it,
+    /// so it shows whether shadows are in a few functions or all over.
+    fn histogram(&self) -> String {
+        const WIDTH: usize = 40;
+        let most = self.buckets.iter().copied().max().unwrap_or(0).max(1);
+        let mut histogram = format!("{}", "Shadows per function:".bright_magenta());
+        for (&(label, _, _), &n) in BUCKETS.iter().zip(&self.buckets) {
+            // Any function at all gets a bar, however short.
+            let len = (n * WIDTH).div_ceil(most);
+            histogram += &format!("\n  {:<4} {} {}", label, "█".repeat(len).bright_cyan(), n);
+        }
+        histogram
+    }
+
+    /// The name with the most shadows, the first one in order on a tie.
+    fn most_shadowed(&self) -> Option<(&str, usize)> {
+        let names = self.names.iter().map(|(name, &n)| (name.as_str(), n));
+        names.min_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)))
+    }
+}
+
+/// Writes the report of each file as it is analyzed, or all of them at the
+/// end for formats that are a single document.
+pub struct Output {
+    format: Format,
+    sort: Sort,
+    group_by: GroupBy,
+    template: String,
+    top: Option<usize>,
+    baseline: HashSet<String>,
+    save_baseline: Option<String>,
+    min_severity: Severity,
+    // The lines of the baseline to save.
+    saved: Vec<String>,
+    targets: Vec<Target>,
+    show_source: bool,
+    histogram: bool,
+    layout: Layout,
+    root: PathBuf,
+    absolute_paths: bool,
+    links: Option<String>,
+    verbose: bool,
+    quiet: u64,
+    show_summary: bool,
+    // Where the report is written.
+    out: Box<dyn Write>,
+    // The reports of the files, when they have to be sorted before they are
+    // written.
+    reports: Vec<FileReport>,
+    files: Vec<JsonFile>,
+    results: Vec<sarif::Result>,
+    issues: Vec<gitlab::Issue>,
+    suites: Vec<junit::Suite>,
+    sections: Vec<markdown::Section>,
+    stats: Stats,
+    start: Instant,
+}
+
+impl Output {
+    /// An output that writes the report to `out`.
+    pub fn new(settings: Settings, out: Box<dyn Write>) -> Self {
+        let format = settings.format;
+        // The worst functions are listed worst first, and a quickfix list
+        // is jumped through from top to bottom.
+        let sort = match (settings.top, settings.sort) {
+            (Some(_), Sort::Density) => Sort::Density,
+            (Some(_), _) => Sort::Count,
+            (None, _) if format == Format::Quickfix => Sort::File,
+            (None, sort) => sort,
+        };
+        let mut output = Output {
+            format,
+            sort,
+            group_by: settings.group_by,
+            template: settings.template.unwrap_or_default(),
+            top: settings.top,
+            baseline: settings.baseline,
+            save_baseline: settings.save_baseline,
+            min_severity: settings.min_severity,
+            saved: Vec::new(),
+            targets: settings.targets,
+            show_source: settings.show_source,
+            histogram: settings.histogram,
+            layout: settings.layout,
+            root: settings.root,
+            absolute_paths: settings.absolute_paths,
+            links: settings.links,
+            verbose: settings.verbose,
+            quiet: settings.quiet,
+            show_summary: settings.summary,
+            out,
+            reports: Vec::new(),
+            files: Vec::new(),
+            results: Vec::new(),
+            issues: Vec::new(),
+            suites: Vec::new(),
+            sections: Vec::new(),
+            stats: Stats::default(),
+            start: Instant::now(),
+        };
+        match format {
+            _ if output.quiet > 0 => {}
+            Format::Csv => output.emit(&format!("{}\n", CSV_COLUMNS.join(","))),
+            Format::Tsv => output.emit(&format!("{}\n", CSV_COLUMNS.join("\t"))),
+            Format::Teamcity => output.emit(&teamcity::inspection_types()),
+            _ => {}
+        }
+        output
+    }
+
+    /// Writes `text` where the report goes.
+    fn emit(&mut self, text: &str) {
+        if let Err(e) = self.out.write_all(text.as_bytes()) {
+            eprintln!("{}: {}", "Unable to write the report".red(), e);
+            std::process::exit(2);
+        }
+    }
+
+    pub fn file(&mut self, mut report: FileReport) {
+        report.file = self.display_path(&report.file);
+        for f in report
+            .funcs
+            .iter_mut()
+            .chain(&mut report.tests)
+            .chain(&mut report.clean)
+        {
+            f.file = f.file.as_deref().map(|file| self.display_path(file));
+            if let Some((file, _, _)) = &mut f.origin {
+                *file = self.display_path(file);
+            }
+        }
+
+        // Every shadow is saved, including the ones of an older baseline.
+        if self.save_baseline.is_some() {
+            self.saved
+                .extend(findings(&report).iter().map(baseline::entry));
+        }
+        if !self.baseline.is_empty() {
+            let known = &self.baseline;
+            retain_findings(&mut report, |finding| {
+                !known.contains(&finding.fingerprint())
+            });
+        }
+        if self.min_severity > Severity::Info {
+            let min = self.min_severity;
+            retain_findings(&mut report, |finding| finding.severity() >= min);
+        }
+
+        self.stats.add(&report);
+        let is_listed = self.verbose && self.format == Format::Human;
+        if (report.is_empty() && !is_listed) || self.quiet > 0 {
+            return;
+        }
+
+        self.sort.functions(&mut report.funcs);
+        self.sort.functions(&mut report.tests);
+        if self.sort == Sort::Line && self.group_by == GroupBy::File {
+            self.write(report);
+        } else {
+            self.reports.push(report);
+        }
+    }
+
+    /// How the file at `path` is shown: relative to the root of the workspace,
+    /// unless it is outside of it or paths are shown absolute. The same file
+    /// is shown the same way however it was found. Paths that aren't files,
+    /// like the names of expanded targets, are left alone.
+    fn display_path(&self, path: &str) -> String {
+        let full = match fs::canonicalize(path) {
+            Ok(full) => full,
+            Err(_) => return path.into(),
+        };
+        match full.strip_prefix(&self.root) {
+            Ok(relative) if !self.absolute_paths => relative.display().to_string(),
+            _ => full.display().to_string(),
+        }
+    }
+
+    /// The file at `path` as shown, to read it again.
+    fn resolve(&self, path: &str) -> PathBuf {
+        self.root.join(path)
+    }
+
+    fn write(&mut self, report: FileReport) {
+        let text = match self.format {
+            Format::Human => self.human(report),
+            Format::Json => {
+                self.files.push(JsonFile::new(report));
+                return;
+            }
+            Format::Jsonl => jsonl(report),
+            Format::Sarif => {
+                let findings = findings(&report);
+                self.results.extend(findings.iter().map(sarif::Result::new));
+                return;
+            }
+            Format::Github => github(report),
+            Format::Azure => azure(report),
+            Format::Gitlab => {
+                let findings = findings(&report);
+                self.issues.extend(findings.iter().map(gitlab::Issue::new));
+                return;
+            }
+            Format::Junit => {
+                self.suites.push(junit::Suite::new(&report));
+                return;
+            }
+            Format::Csv => table(report, ','),
+            Format::Tsv => table(report, '\t'),
+            Format::Short => short(report),
+            Format::Oneline => oneline(report),
+            Format::Quickfix => quickfix(report),
+            Format::Teamcity => findings(&report).iter().map(teamcity::inspection).collect(),
+            Format::Template => findings(&report)
+                .iter()
+                .map(|finding| template::render(&self.template, finding) + "\n")
+                .collect(),
+            Format::Markdown => {
+                self.sections.push(markdown::Section::new(&report));
+                return;
+            }
+            // Only the count is written, once every file is analyzed.
+            Format::Badge => return,
+        };
+        self.emit(&text);
+    }
+
+    /// Writes whatever is left once every file is analyzed, followed by the
+    /// summary of the run. Returns whether any shadows were reported.
+    pub fn finish(mut self) -> bool {
+        let found = self.stats.shadows > 0;
+        if let Some(path) = &self.save_baseline {
+            if let Err(e) = baseline::save(path, &self.saved) {
+                eprintln!("{}: {}", "Unable to save the baseline".red(), e);
+                std::process::exit(2);
+            }
+        }
+        if self.quiet > 0 {
+            if self.quiet == 1 {
+                let line = self.stats.line();
+                self.emit(&format!("{}\n", line));
+            }
+            self.flush();
+            return found;
+        }
+
+        let mut reports = std::mem::take(&mut self.reports);
+        if let Some(n) = self.top {
+            reports = top(reports, n, self.sort);
+        }
+        if self.group_by == GroupBy::File {
+            self.sort.files(&mut reports);
+            for report in reports {
+                self.write(report);
+            }
+        } else {
+            for (name, mut reports) in self.groups(reports) {
+                self.sort.files(&mut reports);
+                if self.format == Format::Human {
+                    let heading = group_heading(self.group_by, &name, &reports);
+                    self.emit(&heading);
+                }
+                for report in reports {
+                    self.write(report);
+                }
+            }
+        }
+
+        if self.show_summary {
+            let summary = self.summary();
+            // The other formats are read by tools, which the summary would
+            // confuse.
+            if self.format == Format::Human {
+                self.emit(&format!("{}\n", summary));
+            } else {
+                eprintln!("{}", summary);
+            }
+        }
+
+        let document = match self.format {
+            Format::Json => {
+                let report = JsonReport {
+                    version: JSON_VERSION,
+                    files: std::mem::take(&mut self.files),
+                };
+                serde_json::to_string_pretty(&report).unwrap() + "\n"
+            }
+            Format::Sarif => {
+                let log = sarif::Log::new(std::mem::take(&mut self.results));
+                serde_json::to_string_pretty(&log).unwrap() + "\n"
+            }
+            Format::Gitlab => serde_json::to_string_pretty(&self.issues).unwrap() + "\n",
+            Format::Junit => junit::document(&self.suites),
+            Format::Markdown => markdown::document(&self.sections),
+            Format::Badge => serde_json::to_string(&Badge::new(self.stats.shadows)).unwrap() + "\n",
+            _ => String::new(),
+        };
+        self.emit(&document);
+        self.flush();
+        found
+    }
+
+    fn flush(&mut self) {
+        if let Err(e) = self.out.flush() {
+            eprintln!("{}: {}", "Unable to write the report".red(), e);
+            std::process::exit(2);
+        }
+    }
+}
+
+impl Output {
+    /// Splits `reports` by the module or crate of their functions, by name.
+    /// Files outside of the workspace are a group of their own.
+    fn groups(&self, reports: Vec<FileReport>) -> BTreeMap<String, Vec<FileReport>> {
+        let mut groups: BTreeMap<String, Vec<FileReport>> = BTreeMap::new();
+        for FileReport {
+            file,
+            funcs,
+            tests,
+            clean,
+        } in reports
+        {
+            let location = workspace::locate(&self.targets, &self.resolve(&file));
+            let group = |f: &Function| match (&location, self.group_by) {
+                (Some((target, _)), GroupBy::Crate) => target.package.clone(),
+                (None, GroupBy::Crate) => "(outside of the workspace)".into(),
+                (Some((target, module)), _) => {
+                    let mut path = vec![target.crate_name()];
+                    path.extend(module.iter().chain(&f.module).cloned());
+                    path.join("::")
+                }
+                (None, _) => {
+                    let path = std::iter::once(&file).chain(&f.module);
+                    path.cloned().collect::<Vec<_>>().join("::")
+                }
+            };
+
+            // The functions of the file in each group, in order.
+            let mut split: BTreeMap<String, FileReport> = BTreeMap::new();
+            let lists = [(funcs, 0), (tests, 1), (clean, 2)];
+            for (list, n) in lists {
+                for f in list {
+                    let part = split.entry(group(&f)).or_insert_with(|| FileReport {
+                        file: file.clone(),
+                        funcs: Vec::new(),
+                        tests: Vec::new(),
+                        clean: Vec::new(),
+                    });
+                    [&mut part.funcs, &mut part.tests, &mut part.clean][n].push(f);
+                }
+            }
+            for (name, part) in split {
+                groups.entry(name).or_default().push(part);
+            }
+        }
+        groups
+    }
+
+    fn summary(&self) -> String {
+        let stats = &self.stats;
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let mut summary = format!(
+            "{} scanned {} {} in {:.2}s\n",
+            "Summary:".bright_magenta(),
+            stats.files,
+            if stats.files == 1 { "file" } else { "files" },
+            elapsed
+        );
+        summary += &format!("  files with shadows:     {}\n", stats.files_with_shadows);
+        summary += &format!("  functions with shadows: {}\n", stats.functions);
+        summary += &format!("  shadowed variables:     {}\n", stats.variables);
+        summary += &format!("  shadows:                {}", stats.shadows);
+        if let Some((name, n)) = stats.most_shadowed() {
+            summary += &format!(
+                "\n  most shadowed:          {} ({})",
+                theme::paint(name, Element::Name).bold(),
+                n
+            );
+        }
+        if self.histogram {
+            summary += &format!("\n{}", self.stats.histogram());
+        }
+        summary
+    }
+}
+
+/// The `n` worst functions of `reports` by `sort`, each in a report of its
+/// own so they can be listed in order across files.
+fn top(reports: Vec<FileReport>, n: usize, sort: Sort) -> Vec<FileReport> {
+    let mut split = Vec::new();
+    for FileReport {
+        file, funcs, tests, ..
+    } in reports
+    {
+        let funcs = funcs.into_iter().map(|f| (f, false));
+        for (f, is_test) in funcs.chain(tests.into_iter().map(|f| (f, true))) {
+            let (funcs, tests) = if is_test {
+                (Vec::new(), vec![f])
+            } else {
+                (vec![f], Vec::new())
+            };
+            split.push(FileReport {
+                file: file.clone(),
+                funcs,
+                tests,
+                clean: Vec::new(),
+            });
+        }
+    }
+    sort.files(&mut split);
+    split.truncate(n);
+    split
+}
+
+/// The heading of a group, with its totals.
+fn group_heading(group_by: GroupBy, name: &str, reports: &[FileReport]) -> String {
+    let mut text = String::new();
+    let functions: usize = reports
+        .iter()
+        .map(|report| report.functions().count())
+        .sum();
+    let shadows: usize = reports
+        .iter()
+        .flat_map(FileReport::functions)
+        .map(Function::shadows)
+        .sum();
+    let kind = match group_by {
+        GroupBy::Crate => "crate",
+        _ => "module",
+    };
+    text += &format!(
+        "{} {}: {} {} in {} {}\n\n",
+        theme::paint(kind, Element::Line).bold(),
+        theme::paint(name, Element::Function).bold(),
+        shadows,
+        if shadows == 1 { "shadow" } else { "shadows" },
+        functions,
+        if functions == 1 {
+            "function"
+        } else {
+            "functions"
+        }
+    );
+    text
+}
+
+impl Output {
+    /// `text` as an OSC 8 hyperlink to `line:col` of `path`, when locations
+    /// are links.
+    fn link(&self, path: &str, line: usize, col: usize, text: String) -> String {
+        let template = match &self.links {
+            Some(template) => template,
+            None => return text,
+        };
+        // Editors need the whole path to open the file.
+        let path = self.resolve(path);
+        let url = template
+            .replace("{path}", &path.to_string_lossy())
+            .replace("{line}", &line.to_string())
+            .replace("{col}", &col.to_string());
+        format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+    }
+
+    fn human(&self, report: FileReport) -> String {
+        let mut out = String::new();
+        let file = self.link(&report.file, 1, 1, report.file.clone());
+        if report.is_empty() {
+            out += &format!("{} has no shadowed variables:\n\n", file);
+        } else {
+            let functions = report.functions();
+            let variables: usize = functions.map(Function::shadowed_variables).sum();
+            let shadows: usize = report.functions().map(Function::shadows).sum();
+            out += &format!(
+                "{} contains shadowed variable(s) ({}, {}):\n\n",
+                file,
+                plural(variables, "variable"),
+                plural(shadows, "shadow")
+            );
+        }
+
+        // The files the functions are in, read the first time they are needed.
+        let mut sources = HashMap::new();
+        let mut print = |out: &mut String, f: &Function| {
+            // The lines of expanded code aren't in any file.
+            if f.origin.is_some() {
+                *out += &format!("{}\n", f);
+                return;
+            }
+            let path = f.file.as_deref().unwrap_or(&report.file);
+            let source = sources
+                .entry(path.to_string())
+                .or_insert_with(|| fs::read_to_string(self.resolve(path)).ok());
+            let lines: Vec<&str> = source.iter().flat_map(|source| source.lines()).collect();
+            *out += &format!(
+                "{}\n",
+                f.render(
+                    &|line, col, text| self.link(path, line, col, text),
+                    &lines,
+                    self.layout
+                )
+            );
+            if !self.show_source {
+                return;
+            }
+            let findings = function_findings(&report.file, f);
+            for findings in by_variable(&findings) {
+                if let Some(source) = &*source {
+                    *out += &format!("{}\n\n", source::excerpt(source, &findings));
+                }
+            }
+        };
+
+        for f in &report.funcs {
+            print(&mut out, f);
+        }
+
+        if !report.tests.is_empty() {
+            out += &format!("  {}\n\n", "in tests:".bright_magenta());
+            for f in &report.tests {
+                print(&mut out, f);
+            }
+        }
+
+        if self.verbose {
+            for f in &report.clean {
+                let mut line = format!(
+                    "  {} {} {} {}",
+                    "✓".green().bold(),
+                    "line:".bright_magenta(),
+                    aligned_location(
+                        f.loc,
+                        f.col,
+                        &|line, col, text| self.link(&report.file, line, col, text),
+                        |loc| loc.bright_magenta()
+                    ),
+                    f.full_name().green()
+                );
+                if f.is_test {
+                    line += &format!(" {}", "test".dimmed());
+                }
+                out += &format!("{}\n", line);
+            }
+            if !report.clean.is_empty() {
+                out += "\n";
+            }
+        }
+        out
+    }
+}
+
+/// What a finding is reported as, for formats that group findings by rule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Rule {
+    DerivedShadow,
+    UnrelatedShadow,
+    ConversionShadow,
+    ReusedName,
+    ItemShadow,
+    FnShadow,
+    AcrossAwait,
+    RebindsScrutinee,
+}
+
+impl Rule {
+    /// The rule of a reported binding, of a variable or hiding an item, with
+    /// its `notes`. Match arms that rebind the scrutinee and shadows across
+    /// an `.await` have a rule of their own, whatever their value.
+    fn of(item_kind: Option<ItemKind>, case: &Case, notes: &[Note]) -> Rule {
+        match (item_kind, case.class) {
+            (Some(ItemKind::Fn), _) => Rule::FnShadow,
+            (Some(_), _) => Rule::ItemShadow,
+            _ if case.is_original => Rule::ReusedName,
+            _ if notes.contains(&Note::RebindsScrutinee) => Rule::RebindsScrutinee,
+            _ if notes.contains(&Note::AcrossAwait) => Rule::AcrossAwait,
+            (None, Some(ShadowClass::Unrelated)) => Rule::UnrelatedShadow,
+            (None, Some(ShadowClass::Conversion)) => Rule::ConversionShadow,
+            (None, _) => Rule::DerivedShadow,
+        }
+    }
+
+    const ALL: [Rule; 8] = [
+        Rule::DerivedShadow,
+        Rule::UnrelatedShadow,
+        Rule::ConversionShadow,
+        Rule::ReusedName,
+        Rule::ItemShadow,
+        Rule::FnShadow,
+        Rule::AcrossAwait,
+        Rule::RebindsScrutinee,
+    ];
+
+    fn id(self) -> &'static str {
+        match self {
+            Rule::DerivedShadow => "derived-shadow",
+            Rule::UnrelatedShadow => "unrelated-shadow",
+            Rule::ConversionShadow => "conversion-shadow",
+            Rule::ReusedName => "reused-name",
+            Rule::ItemShadow => "item-shadow",
+            Rule::FnShadow => "fn-shadow",
+            Rule::AcrossAwait => "across-await",
+            Rule::RebindsScrutinee => "rebinds-scrutinee",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Rule::DerivedShadow => {
+                "A local variable is shadowed by a value computed from it, like `let x = x + 1;`."
+            }
+            Rule::UnrelatedShadow => {
+                "A local variable is shadowed by a value that doesn't use it, like \
+                 `let x = other();`."
+            }
+            Rule::ConversionShadow => {
+                "A local variable is shadowed by an owned or borrowed version of itself, like \
+                 `let x = x.clone();`."
+            }
+            Rule::ReusedName => "A name is bound again in a sibling scope.",
+            Rule::ItemShadow => "A local variable reuses the name of an item of its module.",
+            Rule::FnShadow => {
+                "A local variable reuses the name of a function of its module, which can't be \
+                 called below it."
+            }
+            Rule::AcrossAwait => {
+                "A local variable is shadowed after an `.await`, which may hide a lock guard or \
+                 connection held while the task was suspended."
+            }
+            Rule::RebindsScrutinee => {
+                "A match arm binds the matched variable again, like `match x { x => .. }`, which \
+                 is most likely a misspelled variant or constant."
+            }
+        }
+    }
+
+    /// `warning` or `note`. Shadows with an unrelated value or across an
+    /// `.await`, match arms that rebind the scrutinee and locals that hide an
+    /// item are the ones most likely to be a mistake.
+    fn level(self) -> &'static str {
+        match self {
+            Rule::UnrelatedShadow
+            | Rule::ItemShadow
+            | Rule::FnShadow
+            | Rule::AcrossAwait
+            | Rule::RebindsScrutinee => "warning",
+            _ => "note",
+        }
+    }
+}
+
+/// How likely a finding is to be a mistake, least likely first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Derived shadows, conversions and reused names, which are mostly on
+    /// purpose.
+    Info,
+    /// Unrelated shadows, locals that hide an item, shadowed values that are
+    /// never used and shadows whose type looks different. Types are guessed
+    /// from the syntax, so a type change is never certain enough for an
+    /// error.
+    Warning,
+    /// Shadows in `unsafe` code or across an `.await`, and match arms that
+    /// rebind the scrutinee: the ones shown in bold red.
+    Error,
+}
+
+impl Severity {
+    /// The names the severities are chosen by with `--min-severity`.
+    pub const NAMES: &'static [&'static str] = &["info", "warning", "error"];
+
+    fn of(rule: Rule, notes: &[Note]) -> Severity {
+        let is_error = notes.iter().any(|note| {
+            matches!(
+                note,
+                Note::InUnsafe | Note::AcrossAwait | Note::RebindsScrutinee
+            )
+        });
+        let is_warning = matches!(
+            rule,
+            Rule::UnrelatedShadow | Rule::ItemShadow | Rule::FnShadow
+        ) || notes.iter().any(|note| {
+            matches!(
+                note,
+                Note::ShadowedValueNeverUsed | Note::ChangesApparentType(..)
+            )
+        });
+        match (is_error, is_warning) {
+            (true, _) => Severity::Error,
+            (false, true) => Severity::Warning,
+            (false, false) => Severity::Info,
+        }
+    }
+
+    /// The level of a compiler diagnostic with the severity: `error`,
+    /// `warning` or `note`.
+    fn level(self) -> &'static str {
+        match self {
+            Severity::Info => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err(format!("unknown severity: {}", s)),
+        }
+    }
+}
+
+/// A reported shadow, or reuse of a name, with the function it is in.
+struct Finding<'a> {
+    file: &'a str,
+    func: &'a Function,
+    variable: String,
+    // For a local that reuses the name of an item, the kind of the item.
+    item_kind: Option<ItemKind>,
+    case: &'a Case,
+    // The bindings of the variable, or the item followed by its locals.
+    locs: &'a [Case],
+    // Which of `locs` the case is.
+    index: usize,
+    // For a local that reuses the name of an item, which of the function's
+    // item shadows it is.
+    item: Option<usize>,
+}
+
+impl<'a> Finding<'a> {
+    /// The file the finding's locations refer to, which is the included one
+    /// for a function pulled into the reported file.
+    fn path(&self) -> &'a str {
+        self.func.file.as_deref().unwrap_or(self.file)
+    }
+
+    /// The binding that is shadowed, or whose name is reused.
+    fn shadowed(&self) -> Option<&'a Case> {
+        match self.item_kind {
+            Some(_) => self.locs.first(),
+            None => self
+                .case
+                .shadows
+                .or(self.case.reuses)
+                .map(|i| &self.locs[i]),
+        }
+    }
+
+    fn rule(&self) -> Rule {
+        Rule::of(self.item_kind, self.case, &self.notes())
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::of(self.rule(), &self.notes())
+    }
+
+    fn notes(&self) -> Vec<Note> {
+        match self.item_kind {
+            Some(_) => Vec::new(),
+            None => Note::of(self.case, self.locs),
+        }
+    }
+
+    /// A sentence describing the finding, followed by its notes, like
+    /// `` `x` shadows the binding at 3:9 (apparent type String→usize) ``.
+    fn message(&self) -> String {
+        let at = self
+            .shadowed()
+            .map(|case| format!("{}:{}", case.loc, case.col))
+            .unwrap_or_default();
+        let mut message = match (self.item_kind, self.rule()) {
+            (Some(kind), _) => format!(
+                "`{}` shadows the {} declared at {}",
+                self.variable, kind, at
+            ),
+            (None, Rule::ReusedName) => format!(
+                "`{}` reuses the name of the binding at {} in a sibling scope",
+                self.variable, at
+            ),
+            _ => format!("`{}` shadows the binding at {}", self.variable, at),
+        };
+        for note in self.notes() {
+            if let Note::ReusesName(..) = note {
+                continue;
+            }
+            message += &format!(" ({})", note);
+        }
+        message
+    }
+
+    /// Identifies the finding across runs, even when lines are added or
+    /// removed around it: the function, variable and kind of the finding, and
+    /// which of the variable's bindings it is.
+    fn fingerprint(&self) -> String {
+        let mut key = format!(
+            "{}\0{}\0{}\0{}\0{}",
+            self.path(),
+            self.func.full_name(),
+            self.variable,
+            self.rule().id(),
+            self.index
+        );
+        // Every local that hides an item is the second binding of its own
+        // item shadow, so they are told apart by how many locals with the
+        // name hide the item before it. The first one keeps the fingerprint
+        // it always had.
+        let occurrence = match self.item {
+            Some(position) => self.func.items[..position]
+                .iter()
+                .filter(|item| ident_name(&item.ident) == self.variable)
+                .count(),
+            None => 0,
+        };
+        if occurrence > 0 {
+            key += &format!("\0{}", occurrence);
+        }
+        format!("{:016x}", fnv1a(key.as_bytes()))
+    }
+}
+
+/// The 64 bit FNV-1a hash of `bytes`, which unlike the hasher of the standard
+/// library is the same in every version.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Every reported shadow and reused name of the file, by function.
+fn findings(report: &FileReport) -> Vec<Finding<'_>> {
+    let funcs = report.functions();
+    funcs
+        .flat_map(|func| function_findings(&report.file, func))
+        .collect()
+}
+
+/// The reported shadows and reused names of `func`, in `file`, by variable.
+fn function_findings<'a>(file: &'a str, func: &'a Function) -> Vec<Finding<'a>> {
+    let mut findings = Vec::new();
+    for (ident, count) in func.sorted_vars() {
+        for (index, case) in count.locs.iter().enumerate() {
+            if case.is_reported() {
+                findings.push(Finding {
+                    file,
+                    func,
+                    variable: ident_name(ident),
+                    item_kind: None,
+                    case,
+                    locs: &count.locs,
+                    index,
+                    item: None,
+                });
+            }
+        }
+    }
+
+    for (position, item) in func.items.iter().enumerate() {
+        for (index, case) in item.locs.iter().enumerate().skip(1) {
+            findings.push(Finding {
+                file,
+                func,
+                variable: ident_name(&item.ident),
+                item_kind: Some(item.kind),
+                case,
+                locs: &item.locs,
+                index,
+                item: Some(position),
+            });
+        }
+    }
+    findings
+}
+
+/// Leaves out the findings of `report` that `keep` returns false for.
+/// Functions left without any are listed as clean.
+fn retain_findings<K: Fn(&Finding) -> bool>(report: &mut FileReport, keep: K) {
+    let file = &report.file;
+    let mut clean = Vec::new();
+    for funcs in [&mut report.funcs, &mut report.tests] {
+        for f in funcs.iter_mut() {
+            retain_function_findings(file, f, &keep);
+        }
+        let (kept, cleared): (Vec<Function>, Vec<Function>) = std::mem::take(funcs)
+            .into_iter()
+            .partition(|f| !function_findings(file, f).is_empty());
+        *funcs = kept;
+        clean.extend(cleared);
+    }
+    report.clean.extend(clean);
+    report.clean.sort_by_key(|f| (f.loc, f.col));
+}
+
+fn retain_function_findings<K: Fn(&Finding) -> bool>(file: &str, f: &mut Function, keep: &K) {
+    // The item shadow each finding to leave out is about, if any, its name and
+    // which of the bindings it is. Later bindings of an item are removed
+    // first, so the index of the earlier ones stays the same.
+    let mut found: Vec<(Option<usize>, String, usize)> = function_findings(file, f)
+        .iter()
+        .filter(|finding| !keep(finding))
+        .map(|finding| (finding.item, finding.variable.clone(), finding.index))
+        .collect();
+    found.sort_by_key(|&(_, _, index)| std::cmp::Reverse(index));
+
+    for (item, name, index) in found {
+        if let Some(position) = item {
+            f.items[position].locs.remove(index);
+        } else {
+            let vars = f.vars.iter_mut();
+            for (_, count) in vars.filter(|(ident, _)| ident_name(ident) == name) {
+                count.locs[index].is_allowed = true;
+            }
+        }
+    }
+    f.items.retain(|item| item.locs.len() > 1);
+}
+
+/// Groups `findings` by the variable (or item) of a function they are about.
+/// The findings of a variable are listed next to each other.
+fn by_variable<'f, 'a>(findings: &'f [Finding<'a>]) -> Vec<Vec<&'f Finding<'a>>> {
+    let mut groups: Vec<Vec<&Finding>> = Vec::new();
+    for finding in findings {
+        match groups.last_mut() {
+            Some(group) if std::ptr::eq(group[0].locs, finding.locs) => group.push(finding),
+            _ => groups.push(vec![finding]),
+        }
+    }
+    groups
+}
+
+/// Writes every reported shadow and reused name of the file on a line of its
+/// own.
+fn jsonl(report: FileReport) -> String {
+    let mut text = String::new();
+    for finding in findings(&report) {
+        let mut binding = JsonBinding::new(finding.case, finding.locs);
+        binding.severity = Some(finding.severity());
+        if finding.item_kind.is_some() {
+            binding.shadows = finding.shadowed().map(JsonLocation::of);
+        }
+        let line = JsonFinding {
+            file: finding.file,
+            function: finding.func.full_name(),
+            module: &finding.func.module,
+            variable: finding.variable,
+            item_kind: finding.item_kind.map(|kind| kind.to_string()),
+            binding,
+        };
+        text += &format!("{}\n", serde_json::to_string(&line).unwrap());
+    }
+    text
+}
+
+/// Writes a `::warning` (or `::notice`) workflow command for every finding of
+/// the file, which GitHub Actions shows on the line of the shadow.
+fn github(report: FileReport) -> String {
+    let mut text = String::new();
+    for finding in findings(&report) {
+        let command = match finding.severity() {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "notice",
+        };
+        let end_column = finding.case.col + finding.variable.chars().count();
+        text += &format!(
+            "::{} file={},line={},col={},endColumn={},title={}::{}\n",
+            command,
+            github_escape(finding.path().trim_start_matches("./"), true),
+            finding.case.loc,
+            finding.case.col,
+            end_column,
+            finding.rule().id(),
+            github_escape(&finding.message(), false)
+        );
+    }
+    text
+}
+
+/// Escapes the characters that end a workflow command's message, or one of
+/// its properties.
+fn github_escape(text: &str, is_property: bool) -> String {
+    let text = text
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A");
+    if is_property {
+        text.replace(':', "%3A").replace(',', "%2C")
+    } else {
+        text
+    }
+}
+
+/// A `task.logissue` command per finding. Azure Pipelines only has errors
+/// and warnings, so the findings of the severity info are warnings too.
+fn azure(report: FileReport) -> String {
+    let mut text = String::new();
+    for finding in findings(&report) {
+        let kind = match finding.severity() {
+            Severity::Error => "error",
+            Severity::Warning | Severity::Info => "warning",
+        };
+        text += &format!(
+            "##vso[task.logissue type={};sourcepath={};linenumber={};columnnumber={};code={}]{}\n",
+            kind,
+            azure_escape(finding.path().trim_start_matches("./"), true),
+            finding.case.loc,
+            finding.case.col,
+            finding.rule().id(),
+            azure_escape(&finding.message(), false)
+        );
+    }
+    text
+}
+
+/// Escapes the characters that end a logging command's message, or one of
+/// its properties.
+fn azure_escape(text: &str, is_property: bool) -> String {
+    let text = text
+        .replace('%', "%AZP25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A");
+    if is_property {
+        text.replace(';', "%3B").replace(']', "%5D")
+    } else {
+        text
+    }
+}
+
+fn short(report: FileReport) -> String {
+    let mut text = String::new();
+    for finding in findings(&report) {
+        text += &format!(
+            "{}:{}:{}: {}[{}]: {}\n",
+            finding.path(),
+            finding.case.loc,
+            finding.case.col,
+            finding.severity().level(),
+            finding.rule().id(),
+            finding.message()
+        );
+    }
+    text
+}
+
+/// A line per finding, by line and column, so the same code always gives the
+/// same list.
+fn quickfix(report: FileReport) -> String {
+    let mut findings = findings(&report);
+    findings.sort_by_key(|finding| (finding.case.loc, finding.case.col));
+    let mut text = String::new();
+    for finding in findings {
+        text += &format!(
+            "{}:{}:{}: {}\n",
+            finding.path(),
+            finding.case.loc,
+            finding.case.col,
+            finding.message()
+        );
+    }
+    text
+}
+
+/// A line per finding, like `src/lib.rs:42:9: x in parse: unrelated-shadow of 37:9`,
+/// followed by the ids of its notes. Every finding is on its own line, however
+/// long, so lines can be counted.
+fn oneline(report: FileReport) -> String {
+    let mut text = String::new();
+    for finding in findings(&report) {
+        let mut line = format!(
+            "{}:{}:{}: {} in {}: {}",
+            finding.path(),
+            finding.case.loc,
+            finding.case.col,
+            finding.variable,
+            finding.func.full_name(),
+            finding.rule().id()
+        );
+        if let Some(case) = finding.shadowed() {
+            line += &format!(" of {}:{}", case.loc, case.col);
+        }
+        let notes: Vec<&str> = finding
+            .notes()
+            .iter()
+            .filter(|note| !matches!(note, Note::ReusesName(..)))
+            .map(Note::id)
+            .collect();
+        if !notes.is_empty() {
+            line += &format!(" [{}]", notes.join(","));
+        }
+        text += &format!("{}\n", line);
+    }
+    text
+}
+
+const CSV_COLUMNS: &[&str] = &[
+    "file",
+    "module",
+    "function",
+    "variable",
+    "original_line",
+    "shadowed_line",
+    "shadow_line",
+    "shadow_column",
+    "distance",
+    "classification",
+];
+
+/// Writes a row for every finding of the file, with its values separated by
+/// `separator`.
+fn table(report: FileReport, separator: char) -> String {
+    let mut text = String::new();
+    for finding in findings(&report) {
+        let shadowed = finding.shadowed();
+        let classification = match (finding.item_kind, finding.case.class) {
+            (Some(kind), _) => kind.to_string(),
+            (None, Some(class)) => class.to_string(),
+            (None, None) => finding.rule().id().into(),
+        };
+        let row = [
+            finding.path().to_string(),
+            finding.func.module.join("::"),
+            finding.func.full_name(),
+            finding.variable.clone(),
+            finding.locs[0].loc.to_string(),
+            shadowed.map_or(String::new(), |case| case.loc.to_string()),
+            finding.case.loc.to_string(),
+            finding.case.col.to_string(),
+            shadowed.map_or(String::new(), |case| {
+                finding.case.distance_from(case).to_string()
+            }),
+            classification,
+        ];
+        let row: Vec<String> = row
+            .iter()
+            .map(|value| table_escape(value, separator))
+            .collect();
+        text += &format!("{}\n", row.join(&separator.to_string()));
+    }
+    text
+}
+
+/// Quotes a value that contains the separator, a quote or a line break.
+fn table_escape(value: &str, separator: char) -> String {
+    if value.contains([separator, '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.into()
+    }
+}
+
+/// A shields.io endpoint, which a badge like "shadows | 12" is drawn from.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Badge {
+    schema_version: u32,
+    label: &'static str,
+    message: String,
+    color: &'static str,
+}
+
+impl Badge {
+    fn new(shadows: usize) -> Self {
+        Badge {
+            schema_version: 1,
+            label: "shadows",
+            message: shadows.to_string(),
+            color: match shadows {
+                0 => "brightgreen",
+                1..=9 => "yellow",
+                10..=49 => "orange",
+                _ => "red",
+            },
+        }
+    }
+}
+
+/// Bumped whenever a field of the JSON report is changed or removed, so
+/// scripts can tell whether they understand it. Adding fields doesn't.
+const JSON_VERSION: u32 = 2;
+
+#[derive(Serialize)]
+struct JsonReport {
+    version: u32,
+    files: Vec<JsonFile>,
+}
+
+#[derive(Serialize)]
+struct JsonFile {
+    file: String,
+    functions: Vec<JsonFunction>,
+}
+
+impl JsonFile {
+    fn new(report: FileReport) -> Self {
+        JsonFile {
+            file: report.file.clone(),
+            functions: report.functions().map(JsonFunction::new).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonFunction {
+    name: String,
+    qualified_name: String,
+    module: Vec<String>,
+    line: usize,
+    column: usize,
+    end_line: usize,
+    /// `const`, `static` or `macro_rules` for the bodies that aren't functions.
+    item: Option<&'static str>,
+    is_async: bool,
+    is_test: bool,
+    is_doctest: bool,
+    /// The file the function is in, when it is included into the reported one.
+    included_file: Option<String>,
+    /// Where the function is defined, when analyzing macro expanded code.
+    origin: Option<JsonOrigin>,
+    shadows: usize,
+    /// How many variables and items the shadows are of.
+    shadowed_variables: usize,
+    density: f64,
+    variables: Vec<JsonVariable>,
+    items: Vec<JsonItem>,
+}
+
+impl JsonFunction {
+    fn new(f: &Function) -> Self {
+        let variables = f
+            .sorted_vars()
+            .into_iter()
+            .filter(|(_, count)| count.locs.iter().any(Case::is_reported))
+            .map(|(ident, count)| JsonVariable {
+                name: ident_name(ident),
+                bindings: count
+                    .locs
+                    .iter()
+                    .filter(|case| !case.is_allowed)
+                    .map(|case| JsonBinding::new(case, &count.locs))
+                    .collect(),
+            })
+            .collect();
+
+        let items = f
+            .items
+            .iter()
+            .map(|item| JsonItem {
+                name: ident_name(&item.ident),
+                kind: item.kind.to_string(),
+                declared: JsonLocation::of(&item.locs[0]),
+                bindings: item.locs[1..].iter().map(JsonLocation::of).collect(),
+            })
+            .collect();
+
+        JsonFunction {
+            name: f.name.clone(),
+            qualified_name: f.full_name(),
+            module: f.module.clone(),
+            line: f.loc,
+            column: f.col,
+            end_line: f.end,
+            item: f.item.map(|item| match item {
+                "const" | "static" => item,
+                _ => "macro_rules",
+            }),
+            is_async: f.is_async,
+            is_test: f.is_test,
+            is_doctest: f.is_doctest,
+            included_file: f.file.clone(),
+            origin: f.origin.as_ref().map(|(file, line, column)| JsonOrigin {
+                file: file.clone(),
+                line: *line,
+                column: *column,
+            }),
+            shadows: f.shadows(),
+            shadowed_variables: f.shadowed_variables(),
+            density: f.density(),
+            variables,
+            items,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonOrigin {
+    file: String,
+    line: usize,
+    column: usize,
+}
+
+#[derive(Serialize)]
+struct JsonLocation {
+    line: usize,
+    column: usize,
+}
+
+impl JsonLocation {
+    fn of(case: &Case) -> Self {
+        JsonLocation {
+            line: case.loc,
+            column: case.col,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonVariable {
+    name: String,
+    /// The variable's bindings in order, starting with the original.
+    bindings: Vec<JsonBinding>,
+}
+
+#[derive(Serialize)]
+struct JsonBinding {
+    line: usize,
+    column: usize,
+    is_original: bool,
+    /// The binding this one shadows.
+    shadows: Option<JsonLocation>,
+    /// For an original binding, the binding in a sibling scope whose name it
+    /// reuses.
+    reuses: Option<JsonLocation>,
+    class: Option<ShadowClass>,
+    #[serde(rename = "type")]
+    ty: Option<String>,
+    init: Option<String>,
+    snippet: Option<String>,
+    scope: String,
+    cfgs: Vec<String>,
+    in_macro: bool,
+    /// For a reported binding, how likely it is to be a mistake.
+    severity: Option<Severity>,
+    notes: Vec<JsonNote>,
+}
+
+impl JsonBinding {
+    fn new(case: &Case, locs: &[Case]) -> Self {
+        let notes = Note::of(case, locs);
+        JsonBinding {
+            line: case.loc,
+            column: case.col,
+            is_original: case.is_original,
+            shadows: case.shadows.map(|i| JsonLocation::of(&locs[i])),
+            reuses: case.reuses.map(|i| JsonLocation::of(&locs[i])),
+            class: case.class,
+            ty: case.ty.clone(),
+            init: case.init.as_ref().map(ToString::to_string),
+            snippet: case.snippet.clone(),
+            scope: case.scope.clone(),
+            cfgs: case.cfgs.clone(),
+            in_macro: case.in_macro,
+            severity: case
+                .is_reported()
+                .then(|| Severity::of(Rule::of(None, case, &notes), &notes)),
+            notes: notes
+                .iter()
+                .map(|note| JsonNote {
+                    id: note.id(),
+                    message: note.to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A single shadow, with the function and variable it belongs to.
+#[derive(Serialize)]
+struct JsonFinding<'a> {
+    file: &'a str,
+    function: String,
+    module: &'a [String],
+    variable: String,
+    /// For a local that reuses the name of an item, the kind of the item.
+    item_kind: Option<String>,
+    #[serde(flatten)]
+    binding: JsonBinding,
+}
+
+#[derive(Serialize)]
+struct JsonNote {
+    id: &'static str,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct JsonItem {
+    name: String,
+    kind: String,
+    declared: JsonLocation,
+    /// The locals that reuse the item's name.
+    bindings: Vec<JsonLocation>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fnv1a;
+
+    #[test]
+    fn fnv1a_is_the_published_hash() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+}
@@ -0,0 +1,47 @@
+//! Baselines, the fingerprints of the shadows a code base already has, so
+//! only the ones added since are reported. Each line of a baseline file is a
+//! fingerprint, followed by where the shadow was when it was saved, which is
+//! only there for the people reading it.
+
+use std::collections::HashSet;
+use std::fs;
+
+use super::Finding;
+
+/// The first line of a saved baseline.
+const HEADER: &str = "# cargo light baseline: the fingerprint of each known shadow, \
+                      followed by where it was found.";
+
+/// The fingerprints in the baseline file at `path`.
+pub fn load(path: &str) -> Result<HashSet<String>, String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("unable to read {}: {}", path, e))?;
+    Ok(source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_whitespace().next())
+        .map(String::from)
+        .collect())
+}
+
+/// The line of `finding` in a baseline file.
+pub(super) fn entry(finding: &Finding) -> String {
+    format!(
+        "{} {}:{}:{} {}",
+        finding.fingerprint(),
+        finding.path(),
+        finding.case.loc,
+        finding.case.col,
+        finding.variable
+    )
+}
+
+/// Writes a baseline file with `entries` to `path`.
+pub fn save(path: &str, entries: &[String]) -> Result<(), String> {
+    let mut text = format!("{}\n", HEADER);
+    for entry in entries {
+        text += entry;
+        text += "\n";
+    }
+    fs::write(path, text).map_err(|e| format!("unable to write {}: {}", path, e))
+}
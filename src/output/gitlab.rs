@@ -0,0 +1,46 @@
+//! GitLab Code Quality reports, which merge requests show in their diff.
+
+use super::{Finding, Severity};
+
+#[derive(Serialize)]
+pub struct Issue {
+    description: String,
+    check_name: &'static str,
+    fingerprint: String,
+    severity: &'static str,
+    location: Location,
+}
+
+impl Issue {
+    pub fn new(finding: &Finding) -> Self {
+        let rule = finding.rule();
+        Issue {
+            description: finding.message(),
+            check_name: rule.id(),
+            fingerprint: finding.fingerprint(),
+            severity: match finding.severity() {
+                Severity::Error => "major",
+                Severity::Warning => "minor",
+                Severity::Info => "info",
+            },
+            location: Location {
+                path: finding.path().trim_start_matches("./").into(),
+                lines: Lines {
+                    begin: finding.case.loc,
+                },
+            },
+        }
+    }
+}
+
+/// Paths are relative to the root of the repository.
+#[derive(Serialize)]
+struct Location {
+    path: String,
+    lines: Lines,
+}
+
+#[derive(Serialize)]
+struct Lines {
+    begin: usize,
+}
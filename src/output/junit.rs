@@ -0,0 +1,91 @@
+//! JUnit XML reports, for CI systems that can only show test results. Each
+//! file is a test suite, and each shadowed variable of a function a failed
+//! test case.
+
+use super::{by_variable, findings, FileReport, Finding};
+
+/// The test suite of a file, already written out.
+pub struct Suite {
+    xml: String,
+    tests: usize,
+}
+
+impl Suite {
+    pub fn new(report: &FileReport) -> Self {
+        let findings = findings(report);
+        let cases = by_variable(&findings);
+
+        let mut xml = format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+            escape(&report.file),
+            cases.len(),
+            cases.len()
+        );
+        for case in &cases {
+            xml += &test_case(&report.file, case);
+        }
+        xml += "  </testsuite>\n";
+
+        Suite {
+            xml,
+            tests: cases.len(),
+        }
+    }
+}
+
+/// A failed test case for the findings of one variable, with each of them on
+/// a line of the failure's body.
+fn test_case(file: &str, findings: &[&Finding]) -> String {
+    let first = findings[0];
+    let message = match findings.len() {
+        1 => first.message(),
+        n => format!("`{}` is shadowed {} times", first.variable, n),
+    };
+    let body: Vec<String> = findings
+        .iter()
+        .map(|finding| {
+            let case = finding.case;
+            format!(
+                "{}:{}:{}: {}",
+                finding.path(),
+                case.loc,
+                case.col,
+                finding.message()
+            )
+        })
+        .collect();
+
+    format!(
+        "    <testcase classname=\"{}\" name=\"{}: {}\">\n      \
+         <failure message=\"{}\" type=\"{}\">{}</failure>\n    </testcase>\n",
+        escape(file),
+        escape(&first.func.full_name()),
+        escape(&first.variable),
+        escape(&message),
+        first.rule().id(),
+        escape(&body.join("\n"))
+    )
+}
+
+/// The whole report, with the suite of every file.
+pub fn document(suites: &[Suite]) -> String {
+    let tests: usize = suites.iter().map(|suite| suite.tests).sum();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml += &format!(
+        "<testsuites name=\"cargo-light\" tests=\"{}\" failures=\"{}\">\n",
+        tests, tests
+    );
+    for suite in suites {
+        xml += &suite.xml;
+    }
+    xml += "</testsuites>\n";
+    xml
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,105 @@
+//! Markdown summaries, made to be posted as a comment on a pull request: a
+//! table of the functions with shadows, followed by the shadows of each
+//! function in a collapsed section.
+
+use super::{findings, FileReport};
+
+/// The rows and details of the functions of a file, already written out.
+pub struct Section {
+    rows: Vec<String>,
+    details: Vec<String>,
+    shadows: usize,
+}
+
+impl Section {
+    pub fn new(report: &FileReport) -> Self {
+        let findings = findings(report);
+        let mut section = Section {
+            rows: Vec::new(),
+            details: Vec::new(),
+            shadows: findings.len(),
+        };
+
+        for f in report.functions() {
+            let name = f.full_name();
+            let shadows = f.shadows();
+            section.rows.push(format!(
+                "| `{}` | `{}` | {} | {} | {:.1} |",
+                cell_escape(&report.file),
+                cell_escape(&name),
+                f.loc,
+                shadows,
+                f.density()
+            ));
+
+            let mut details = format!(
+                "<details>\n<summary><code>{}</code>: <code>{}</code> ({} {})</summary>\n\n",
+                html_escape(&report.file),
+                html_escape(&name),
+                shadows,
+                if shadows == 1 { "shadow" } else { "shadows" }
+            );
+            let func_findings = findings
+                .iter()
+                .filter(|finding| std::ptr::eq(finding.func, f));
+            for finding in func_findings {
+                details += &format!(
+                    "- line {}:{}: {}\n",
+                    finding.case.loc,
+                    finding.case.col,
+                    cell_escape(&finding.message())
+                );
+            }
+            details += "\n</details>\n";
+            section.details.push(details);
+        }
+        section
+    }
+}
+
+/// The whole summary, with the sections of every file.
+pub fn document(sections: &[Section]) -> String {
+    let shadows: usize = sections.iter().map(|section| section.shadows).sum();
+    let functions: usize = sections.iter().map(|section| section.rows.len()).sum();
+    if functions == 0 {
+        return "## cargo light\n\nNo shadowed variables found.\n".into();
+    }
+
+    let mut markdown = format!(
+        "## cargo light\n\nFound {} {} in {} {}.\n\n",
+        shadows,
+        if shadows == 1 { "shadow" } else { "shadows" },
+        functions,
+        if functions == 1 {
+            "function"
+        } else {
+            "functions"
+        }
+    );
+    markdown += "| File | Function | Line | Shadows | Per 100 lines |\n";
+    markdown += "| --- | --- | ---: | ---: | ---: |\n";
+    for row in sections.iter().flat_map(|section| &section.rows) {
+        markdown += row;
+        markdown += "\n";
+    }
+    markdown += "\n";
+    for details in sections.iter().flat_map(|section| &section.details) {
+        markdown += details;
+        markdown += "\n";
+    }
+    markdown
+}
+
+/// A `|` ends a table cell, even in a code span, unless it is escaped. Lines
+/// of the details are escaped the same way, so notes read the same in both.
+fn cell_escape(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+/// Summaries are HTML, where names like `<Foo as Display>::fmt` would be
+/// taken for a tag.
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
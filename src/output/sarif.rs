@@ -0,0 +1,227 @@
+//! SARIF 2.1.0 logs, which GitHub code scanning, Azure DevOps and many other
+//! dashboards can show findings from.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::{Case, Finding, Rule};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+#[derive(Serialize)]
+pub struct Log {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run>,
+}
+
+impl Log {
+    pub fn new(results: Vec<Result>) -> Self {
+        let driver = Driver {
+            name: "cargo-light",
+            information_uri: "https://github.com/fisherdarling/cargo-light",
+            version: env!("CARGO_PKG_VERSION"),
+            rules: Rule::ALL
+                .iter()
+                .map(|&rule| RuleDescriptor::new(rule))
+                .collect(),
+        };
+        Log {
+            schema: SCHEMA,
+            version: "2.1.0",
+            runs: vec![Run {
+                tool: Tool { driver },
+                results,
+            }],
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Run {
+    tool: Tool,
+    results: Vec<Result>,
+}
+
+#[derive(Serialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver {
+    name: &'static str,
+    information_uri: &'static str,
+    version: &'static str,
+    rules: Vec<RuleDescriptor>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RuleDescriptor {
+    id: &'static str,
+    short_description: Message,
+    default_configuration: Configuration,
+}
+
+impl RuleDescriptor {
+    fn new(rule: Rule) -> Self {
+        RuleDescriptor {
+            id: rule.id(),
+            short_description: Message::new(rule.description()),
+            default_configuration: Configuration {
+                level: rule.level(),
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Configuration {
+    level: &'static str,
+}
+
+#[derive(Serialize)]
+struct Message {
+    text: String,
+}
+
+impl Message {
+    fn new(text: &str) -> Self {
+        Message { text: text.into() }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Result {
+    rule_id: &'static str,
+    rule_index: usize,
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+    /// The binding that is shadowed.
+    related_locations: Vec<Location>,
+    partial_fingerprints: BTreeMap<&'static str, String>,
+    properties: Properties,
+}
+
+impl Result {
+    pub fn new(finding: &Finding) -> Self {
+        let rule = finding.rule();
+        let mut location = Location::new(finding, finding.case, None);
+        location.logical_locations = vec![LogicalLocation {
+            fully_qualified_name: finding.func.full_name(),
+            kind: "function",
+        }];
+        let related = finding
+            .shadowed()
+            .map(|case| Location::new(finding, case, Some(0)));
+
+        let mut fingerprints = BTreeMap::new();
+        fingerprints.insert("shadow/v1", finding.fingerprint());
+
+        Result {
+            rule_id: rule.id(),
+            rule_index: Rule::ALL.iter().position(|&r| r == rule).unwrap(),
+            level: finding.severity().level(),
+            message: Message::new(&finding.message()),
+            locations: vec![location],
+            related_locations: related.into_iter().collect(),
+            partial_fingerprints: fingerprints,
+            properties: Properties {
+                notes: finding.notes().iter().map(|note| note.id()).collect(),
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Properties {
+    notes: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<usize>,
+    physical_location: PhysicalLocation,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    logical_locations: Vec<LogicalLocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<Message>,
+}
+
+impl Location {
+    /// The name of the finding's variable at `case`, which is the shadowed
+    /// binding when the location has an `id`.
+    fn new(finding: &Finding, case: &Case, id: Option<usize>) -> Self {
+        let end_column = case.col + finding.variable.chars().count();
+        Location {
+            id,
+            physical_location: PhysicalLocation {
+                artifact_location: artifact_location(finding.path()),
+                region: Region {
+                    start_line: case.loc,
+                    start_column: case.col,
+                    end_line: case.loc,
+                    end_column,
+                },
+            },
+            logical_locations: Vec::new(),
+            message: id.map(|_| Message::new("shadowed binding")),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    region: Region,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactLocation {
+    uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uri_base_id: Option<&'static str>,
+}
+
+/// Relative paths are relative to the checked out source, which is how code
+/// scanning platforms want them. Absolute paths become `file://` URIs.
+fn artifact_location(path: &str) -> ArtifactLocation {
+    let uri = path.replace('\\', "/");
+    if Path::new(path).is_absolute() {
+        ArtifactLocation {
+            uri: format!("file://{}", uri),
+            uri_base_id: None,
+        }
+    } else {
+        ArtifactLocation {
+            uri: uri.trim_start_matches("./").into(),
+            uri_base_id: Some("%SRCROOT%"),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: usize,
+    start_column: usize,
+    end_line: usize,
+    /// The column after the variable's name, as SARIF regions end before it.
+    end_column: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LogicalLocation {
+    fully_qualified_name: String,
+    kind: &'static str,
+}
@@ -0,0 +1,104 @@
+//! Excerpts of the source of a shadowed variable, with its bindings
+//! underlined and labeled like in the diagnostics of rustc.
+
+use colored::Colorize;
+
+use super::{Case, Finding, Note, Rule};
+
+/// The lines of `source` with the bindings of the variable that `findings`
+/// are about: its first binding, the bindings that are shadowed and the
+/// shadows, which are underlined with `^`.
+pub fn excerpt(source: &str, findings: &[&Finding]) -> String {
+    let first = findings[0];
+
+    // The underlined bindings, with whether they are a finding and their
+    // label.
+    let mut marks: Vec<(&Case, bool, String)> = Vec::new();
+    for finding in findings {
+        marks.push((finding.case, true, label(finding)));
+    }
+    let others = findings
+        .iter()
+        .filter_map(|finding| finding.shadowed())
+        .chain(first.locs.first());
+    for case in others {
+        if !marks
+            .iter()
+            .any(|(marked, _, _)| std::ptr::eq(*marked, case))
+        {
+            let label = match first.item_kind {
+                Some(kind) => format!("{} declared here", kind),
+                None if std::ptr::eq(case, &first.locs[0]) => "first bound here".into(),
+                None => "bound here".into(),
+            };
+            marks.push((case, false, label));
+        }
+    }
+    marks.sort_by_key(|(case, _, _)| (case.loc, case.col));
+
+    let last_line = marks.last().map_or(0, |(case, _, _)| case.loc);
+    let gutter = " ".repeat(last_line.to_string().len());
+    let bar = "|".blue().bold();
+    let lines: Vec<&str> = source.lines().collect();
+
+    let mut excerpt = format!(
+        "    {}{} {}:{}:{}\n    {} {}\n",
+        gutter,
+        "-->".blue().bold(),
+        first.path(),
+        first.case.loc,
+        first.case.col,
+        gutter,
+        bar
+    );
+    let mut prev_line = None;
+    for (n, (case, is_finding, label)) in marks.iter().enumerate() {
+        if prev_line != Some(case.loc) {
+            if prev_line.is_some_and(|prev| case.loc > prev + 1) {
+                excerpt += &format!("    {}\n", "...".blue().bold());
+            }
+            let text = lines.get(case.loc.wrapping_sub(1)).unwrap_or(&"");
+            let line = format!("{:>width$}", case.loc, width = gutter.len());
+            excerpt += &format!("    {} {} {}\n", line.blue().bold(), bar, text);
+            prev_line = Some(case.loc);
+        }
+
+        let text = lines.get(case.loc.wrapping_sub(1)).unwrap_or(&"");
+        let mark = if *is_finding { "^" } else { "-" };
+        let underline = mark.repeat(first.variable.chars().count());
+        excerpt += &format!(
+            "    {} {} {}{}",
+            gutter,
+            bar,
+            indent(text, case.col),
+            case.paint(&format!("{} {}", underline, label))
+        );
+        if n + 1 < marks.len() {
+            excerpt += "\n";
+        }
+    }
+    excerpt
+}
+
+/// What a finding's binding is labeled with, followed by its notes.
+fn label(finding: &Finding) -> String {
+    let mut label = match (finding.item_kind, finding.rule()) {
+        (Some(kind), _) => format!("shadows the {}", kind),
+        (None, Rule::ReusedName) => "name reused here".into(),
+        _ => "shadowed here".into(),
+    };
+    for note in finding.notes() {
+        if let Note::ReusesName(..) = note {
+            continue;
+        }
+        label += &format!(" ({})", note);
+    }
+    label
+}
+
+/// Blanks up to the 1-based column `col` of `text`, keeping its tabs so the
+/// underline lines up with the binding.
+fn indent(text: &str, col: usize) -> String {
+    let prefix = text.chars().take(col.saturating_sub(1));
+    prefix.map(|c| if c == '\t' { '\t' } else { ' ' }).collect()
+}
@@ -0,0 +1,53 @@
+//! TeamCity service messages, which put the shadows in the Inspections tab of
+//! a build without any plugin.
+
+use super::{Finding, Rule, Severity};
+
+/// The `inspectionType` messages that describe every rule, which TeamCity
+/// wants before the inspections that refer to them.
+pub fn inspection_types() -> String {
+    let mut text = String::new();
+    for rule in Rule::ALL.iter() {
+        text += &format!(
+            "##teamcity[inspectionType id='{}' name='{}' description='{}' category='Shadowing']\n",
+            rule.id(),
+            rule.id(),
+            escape(rule.description())
+        );
+    }
+    text
+}
+
+/// The `inspection` message of a finding.
+pub fn inspection(finding: &Finding) -> String {
+    let severity = match finding.severity() {
+        Severity::Error => "ERROR",
+        Severity::Warning => "WARNING",
+        Severity::Info => "INFO",
+    };
+    format!(
+        "##teamcity[inspection typeId='{}' message='{}' file='{}' line='{}' SEVERITY='{}']\n",
+        finding.rule().id(),
+        escape(&finding.message()),
+        escape(finding.path().trim_start_matches("./")),
+        finding.case.loc,
+        severity
+    )
+}
+
+/// Escapes the characters that end a value in a service message, with `|`.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '|' => escaped += "||",
+            '\'' => escaped += "|'",
+            '[' => escaped += "|[",
+            ']' => escaped += "|]",
+            '\n' => escaped += "|n",
+            '\r' => escaped += "|r",
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
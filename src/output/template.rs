@@ -0,0 +1,139 @@
+//! Reports in a format of the user's own: a line per finding, written from a
+//! template like `{file}:{line}:{col} {var} shadows {orig_line}`.
+
+use super::{Finding, Note};
+
+/// The placeholders a template can have, and what they are replaced with.
+pub const PLACEHOLDERS: &[(&str, &str)] = &[
+    ("file", "the path of the file"),
+    ("line", "the line of the shadow"),
+    ("col", "the column of the shadow"),
+    ("end_col", "the column after the name of the shadow"),
+    ("var", "the name of the variable"),
+    (
+        "function",
+        "the name of the function, with its module and type",
+    ),
+    ("module", "the module the function is in"),
+    ("rule", "the rule of the shadow, like unrelated-shadow"),
+    ("level", "error, warning or note"),
+    ("severity", "error, warning or info"),
+    (
+        "class",
+        "derived, unrelated or conversion, or the kind of item",
+    ),
+    ("message", "the sentence describing the shadow"),
+    (
+        "notes",
+        "the ids of the notes on the shadow, separated by commas",
+    ),
+    ("orig_line", "the line of the variable's first binding"),
+    ("orig_col", "the column of the variable's first binding"),
+    ("shadowed_line", "the line of the binding that is shadowed"),
+    ("shadowed_col", "the column of the binding that is shadowed"),
+    (
+        "distance",
+        "the lines between the shadowed statement and the shadow",
+    ),
+    (
+        "fingerprint",
+        "the fingerprint of the shadow, as in baselines",
+    ),
+];
+
+/// Pieces of a template: literal text, or the name of a placeholder.
+enum Piece<'t> {
+    Text(&'t str),
+    Placeholder(&'t str),
+}
+
+/// Splits `template` into its pieces. `{{` and `}}` are literal braces.
+fn parse(template: &str) -> Result<Vec<Piece<'_>>, String> {
+    let mut pieces = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        if start > 0 {
+            pieces.push(Piece::Text(&rest[..start]));
+        }
+        let brace = &rest[start..start + 1];
+        rest = &rest[start + 1..];
+        if let Some(after) = rest.strip_prefix(brace) {
+            pieces.push(Piece::Text(brace));
+            rest = after;
+            continue;
+        }
+        if brace == "}" {
+            return Err("unmatched `}`, write `}}` for a literal brace".into());
+        }
+        let end = rest
+            .find('}')
+            .ok_or_else(|| "unclosed `{`, write `{{` for a literal brace".to_string())?;
+        let name = &rest[..end];
+        if !PLACEHOLDERS.iter().any(|&(known, _)| known == name) {
+            return Err(format!("unknown placeholder `{{{}}}`", name));
+        }
+        pieces.push(Piece::Placeholder(name));
+        rest = &rest[end + 1..];
+    }
+    if !rest.is_empty() {
+        pieces.push(Piece::Text(rest));
+    }
+    Ok(pieces)
+}
+
+/// Whether `template` can be rendered, with the reason it can't.
+pub fn check(template: &str) -> Result<(), String> {
+    parse(template).map(|_| ())
+}
+
+/// The line of `finding` written from `template`, which has been checked.
+pub(super) fn render(template: &str, finding: &Finding) -> String {
+    let pieces = parse(template).unwrap_or_default();
+    let mut line = String::new();
+    for piece in pieces {
+        match piece {
+            Piece::Text(text) => line += text,
+            Piece::Placeholder(name) => line += &value(name, finding),
+        }
+    }
+    line
+}
+
+fn value(name: &str, finding: &Finding) -> String {
+    let case = finding.case;
+    let shadowed = finding.shadowed();
+    match name {
+        "file" => finding.path().into(),
+        "line" => case.loc.to_string(),
+        "col" => case.col.to_string(),
+        "end_col" => (case.col + finding.variable.chars().count()).to_string(),
+        "var" => finding.variable.clone(),
+        "function" => finding.func.full_name(),
+        "module" => finding.func.module.join("::"),
+        "rule" => finding.rule().id().into(),
+        "level" => finding.severity().level().into(),
+        "severity" => format!("{:?}", finding.severity()).to_lowercase(),
+        "class" => match (finding.item_kind, case.class) {
+            (Some(kind), _) => kind.to_string(),
+            (None, Some(class)) => class.to_string(),
+            (None, None) => String::new(),
+        },
+        "message" => finding.message(),
+        "notes" => {
+            let notes = finding.notes();
+            let notes = notes
+                .iter()
+                .filter(|note| !matches!(note, Note::ReusesName(..)));
+            notes.map(Note::id).collect::<Vec<_>>().join(",")
+        }
+        "orig_line" => finding.locs[0].loc.to_string(),
+        "orig_col" => finding.locs[0].col.to_string(),
+        "shadowed_line" => shadowed.map_or(String::new(), |case| case.loc.to_string()),
+        "shadowed_col" => shadowed.map_or(String::new(), |case| case.col.to_string()),
+        "distance" => shadowed.map_or(String::new(), |shadowed| {
+            case.distance_from(shadowed).to_string()
+        }),
+        "fingerprint" => finding.fingerprint(),
+        _ => String::new(),
+    }
+}
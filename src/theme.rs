@@ -0,0 +1,158 @@
+//! The colors of the report in the terminal. The default ones are picked for
+//! dark backgrounds, the `[theme]` table of the config file can switch to the
+//! `light` preset and override the color of each element:
+//!
+//! ```toml
+//! [theme]
+//! preset = "light"
+//! original = "blue"
+//! derived = "bright magenta"
+//! ```
+
+use colored::{Color, ColoredString, Colorize};
+
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+/// The parts of the report that have a color of their own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Element {
+    /// The location of the first binding of a name.
+    Original,
+    /// The location of a shadow derived from the shadowed value.
+    Derived,
+    /// The location of a shadow that converts the shadowed value.
+    Conversion,
+    /// The location of a shadow unrelated to the shadowed value.
+    Unrelated,
+    /// The name of a function, module or crate.
+    Function,
+    /// The location of a function, and the `line:` and `module` labels.
+    Line,
+    /// The name of a variable.
+    Name,
+    /// How many times a variable is bound.
+    Count,
+    /// The notes of a shadow.
+    Note,
+    /// The notes of the shadows most likely to be a mistake, like the ones
+    /// in `unsafe` code.
+    SevereNote,
+    /// The kind of an item that a local hides.
+    Item,
+}
+
+/// The names elements are configured by, in the order of `Element`.
+const NAMES: &[&str] = &[
+    "original",
+    "derived",
+    "conversion",
+    "unrelated",
+    "function",
+    "line",
+    "name",
+    "count",
+    "note",
+    "severe-note",
+    "item",
+];
+
+/// The names of the built-in themes.
+const PRESETS: &[&str] = &["dark", "light"];
+
+/// The color of every element.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    colors: [Color; 11],
+}
+
+impl Theme {
+    /// The default colors, for dark backgrounds.
+    fn dark() -> Self {
+        Theme {
+            colors: [
+                Color::Cyan,
+                Color::Yellow,
+                Color::Green,
+                Color::Red,
+                Color::BrightGreen,
+                Color::BrightMagenta,
+                Color::BrightWhite,
+                Color::BrightCyan,
+                Color::Yellow,
+                Color::BrightRed,
+                Color::BrightRed,
+            ],
+        }
+    }
+
+    /// Colors without yellow or bright white, which are hard to read on
+    /// light backgrounds.
+    fn light() -> Self {
+        Theme {
+            colors: [
+                Color::Blue,
+                Color::Magenta,
+                Color::Green,
+                Color::Red,
+                Color::Green,
+                Color::Blue,
+                Color::Black,
+                Color::Blue,
+                Color::Magenta,
+                Color::Red,
+                Color::Red,
+            ],
+        }
+    }
+
+    /// The theme of the `[theme]` table of a config file: its `preset`, if
+    /// any, with the colors it names. Colors are the ones terminals have,
+    /// like `blue` or `bright blue`.
+    pub fn from_table(table: &BTreeMap<String, String>) -> Result<Self, String> {
+        let mut theme = match table.get("preset").map(String::as_str) {
+            None | Some("dark") => Theme::dark(),
+            Some("light") => Theme::light(),
+            Some(preset) => {
+                return Err(format!(
+                    "unknown theme preset: {} (expected one of {})",
+                    preset,
+                    PRESETS.join(", ")
+                ))
+            }
+        };
+        for (key, value) in table.iter().filter(|(key, _)| *key != "preset") {
+            let index = NAMES.iter().position(|name| name == key).ok_or_else(|| {
+                format!(
+                    "unknown theme element: {} (expected one of preset, {})",
+                    key,
+                    NAMES.join(", ")
+                )
+            })?;
+            theme.colors[index] = value
+                .replace(['_', '-'], " ")
+                .parse()
+                .map_err(|()| format!("unknown color for {}: {}", key, value))?;
+        }
+        Ok(theme)
+    }
+
+    fn color(&self, element: Element) -> Color {
+        self.colors[element as usize]
+    }
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Makes `theme` the one the report is painted in. Only the first call has
+/// an effect.
+pub fn set(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+/// `text` in the color of `element`, in the theme that was set or the
+/// default one.
+pub fn paint(text: &str, element: Element) -> ColoredString {
+    let color = THEME.get_or_init(Theme::dark).color(element);
+    text.color(color)
+}
@@ -0,0 +1,169 @@
+//! The packages of the cargo workspace being analyzed and their targets, as
+//! told by `cargo metadata`.
+
+use serde_json::Value;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A package of the workspace.
+pub struct Package {
+    pub name: String,
+    /// The directory of the package's `Cargo.toml`, its sources are below it.
+    pub dir: PathBuf,
+}
+
+/// A lib, bin, example, test or bench target of a package.
+#[derive(Clone)]
+pub struct Target {
+    pub package: String,
+    pub name: String,
+    /// The first kind of the target, like `lib` or `bin`.
+    pub kind: String,
+    /// The target's root source file.
+    pub src_path: PathBuf,
+}
+
+impl Target {
+    /// The name the target's crate is known by in paths.
+    pub fn crate_name(&self) -> String {
+        self.name.replace('-', "_")
+    }
+}
+
+/// The root directory of the workspace at `dir`, the one with the workspace's
+/// `Cargo.toml`, if `dir` is in one.
+pub fn root(dir: &str) -> Option<PathBuf> {
+    let output = Command::new("cargo")
+        .args(["locate-project", "--workspace", "--message-format", "plain"])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let manifest = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    manifest.parent()?.canonicalize().ok()
+}
+
+/// The output of `cargo metadata` for the workspace at `dir`, without its
+/// dependencies.
+fn metadata(dir: &str) -> Result<Value, String> {
+    let output = Command::new("cargo")
+        .args(["metadata", "--no-deps", "--format-version", "1"])
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("unable to run `cargo metadata`: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "`cargo metadata` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("unable to read `cargo metadata` output: {}", e))
+}
+
+/// Every package in the workspace at `dir`.
+pub fn packages(dir: &str) -> Result<Vec<Package>, String> {
+    let metadata = metadata(dir)?;
+    let packages = metadata["packages"].as_array().cloned().unwrap_or_default();
+    Ok(packages
+        .iter()
+        .filter_map(|package| {
+            let manifest = Path::new(package["manifest_path"].as_str()?);
+            let dir = manifest.parent()?;
+            Some(Package {
+                name: package["name"].as_str().unwrap_or_default().into(),
+                dir: dir.canonicalize().unwrap_or_else(|_| dir.into()),
+            })
+        })
+        .collect())
+}
+
+/// The packages chosen by name in `names`, or all of them with `all`, but
+/// not the ones in `exclude`, like `cargo build -p` and `--workspace` do.
+pub fn select<'p>(
+    packages: &'p [Package],
+    names: &[&str],
+    all: bool,
+    exclude: &[&str],
+) -> Result<Vec<&'p Package>, String> {
+    if let Some(name) = names
+        .iter()
+        .find(|name| !packages.iter().any(|package| package.name == **name))
+    {
+        return Err(format!("package `{}` is not in the workspace", name));
+    }
+    Ok(packages
+        .iter()
+        .filter(|package| all || names.contains(&package.name.as_str()))
+        .filter(|package| !exclude.contains(&package.name.as_str()))
+        .collect())
+}
+
+/// The package that `file` belongs to: the one in the closest directory
+/// above it, as packages can be nested in each other.
+pub fn package_of<'p>(packages: &'p [Package], file: &Path) -> Option<&'p Package> {
+    let file = file.canonicalize().ok()?;
+    packages
+        .iter()
+        .filter(|package| file.starts_with(&package.dir))
+        .max_by_key(|package| package.dir.components().count())
+}
+
+/// The targets of every package in the workspace at `dir`.
+pub fn targets(dir: &str) -> Result<Vec<Target>, String> {
+    let metadata = metadata(dir)?;
+
+    let mut found = Vec::new();
+    let packages = metadata["packages"].as_array().cloned().unwrap_or_default();
+    for package in &packages {
+        let targets = package["targets"].as_array().cloned().unwrap_or_default();
+        for target in &targets {
+            let src_path = PathBuf::from(target["src_path"].as_str().unwrap_or_default());
+            found.push(Target {
+                package: package["name"].as_str().unwrap_or_default().into(),
+                name: target["name"].as_str().unwrap_or_default().into(),
+                kind: target["kind"][0].as_str().unwrap_or_default().into(),
+                src_path: src_path.canonicalize().unwrap_or(src_path),
+            });
+        }
+    }
+    Ok(found)
+}
+
+/// The target that `file` belongs to, with the path of the module the file is
+/// for. A file belongs to the target whose root file is in the closest
+/// directory above it, the first one listed if there are several.
+pub fn locate<'t>(targets: &'t [Target], file: &Path) -> Option<(&'t Target, Vec<String>)> {
+    let file = file.canonicalize().ok()?;
+    let (target, root) = targets
+        .iter()
+        .filter_map(|target| {
+            let root = target.src_path.parent()?;
+            Some((target, root)).filter(|_| file.starts_with(root))
+        })
+        .min_by_key(|(_, root)| std::cmp::Reverse(root.components().count()))?;
+    Some((target, file_module(root, &target.src_path, &file)))
+}
+
+/// The path of the module that a file under `root` is for, like `a::b` for
+/// `root/a/b.rs` or `root/a/b/mod.rs`. `src_path`, the target's root file, is
+/// the crate root.
+pub fn file_module(root: &Path, src_path: &Path, file: &Path) -> Vec<String> {
+    if file == src_path {
+        return Vec::new();
+    }
+    let relative = file.strip_prefix(root).unwrap_or(file).with_extension("");
+    let mut path: Vec<String> = relative
+        .iter()
+        .map(|part| part.to_string_lossy().into_owned())
+        .collect();
+    if path.last().is_some_and(|last| last == "mod") {
+        path.pop();
+    }
+    path
+}
@@ -0,0 +1,940 @@
+//! What cargo light notes about the shadows it finds, and the options that
+//! choose which ones it reports.
+
+mod common;
+
+use common::*;
+
+#[test]
+fn locals_that_hide_consts_and_statics_are_reported_with_check_items() {
+    let source = "
+const MAX: usize = 10;
+#[allow(non_upper_case_globals)]
+static count: u8 = 0;
+
+#[allow(non_snake_case)]
+fn f() {
+    let MAX = 1;
+    let count = 2;
+    drop((MAX, count));
+}
+";
+    assert_eq!(short("items-default", source, &[]), Vec::<String>::new());
+    assert_eq!(
+        short("items", source, &["--check-items"]),
+        [
+            "warning[item-shadow]: `MAX` shadows the const declared at 2:7",
+            "warning[item-shadow]: `count` shadows the static declared at 4:8",
+        ]
+    );
+}
+
+#[test]
+fn locals_that_hide_imported_names_are_reported() {
+    let source = "
+use std::io::Result;
+use std::mem::drop as release;
+
+#[allow(non_snake_case)]
+fn f() {
+    let Result = 1;
+    let release = 2;
+    drop((Result, release));
+}
+";
+    assert_eq!(
+        short("imports", source, &[]),
+        [
+            "warning[item-shadow]: `Result` shadows the use declared at 2:14",
+            "warning[item-shadow]: `release` shadows the use declared at 3:23",
+        ]
+    );
+}
+
+#[test]
+fn default_methods_of_traits_are_analyzed() {
+    let source = "
+trait T {
+    fn required(&self);
+
+    fn provided(&self) {
+        let a = 1;
+        let a = a + 1;
+        drop(a);
+    }
+}
+";
+    let report = report("trait-methods", source, &["--color", "never"]);
+    assert!(report.contains("T::provided"), "{}", report);
+    assert_eq!(
+        short("trait-methods-short", source, &[]),
+        ["note[derived-shadow]: `a` shadows the binding at 6:13"]
+    );
+}
+
+#[test]
+fn async_blocks_are_scopes_of_their_async_function() {
+    let source = "
+async fn f() {
+    let b = 1;
+    let fut = async move {
+        let b = b + 1;
+        drop(b);
+    };
+    fut.await;
+    let c = 1;
+    drop(c);
+}
+";
+    let report = report("async", source, &["--color", "never"]);
+    assert!(
+        report.contains("let b = b + 1   (in fn f > async block)"),
+        "{}",
+        report
+    );
+    assert!(report.contains(" async lines 2–11 "), "{}", report);
+}
+
+#[test]
+fn code_inside_of_macros_is_analyzed_with_macros() {
+    let source = "
+fn f() {
+    let v = 1;
+    println!(\"{}\", { let v = v + 1; v });
+}
+";
+    assert_eq!(short("macros-default", source, &[]), Vec::<String>::new());
+    assert_eq!(
+        short("macros", source, &["--macros"]),
+        ["note[derived-shadow]: `v` shadows the binding at 3:9"]
+    );
+    let report = report("macros-human", source, &["--macros", "--color", "never"]);
+    assert!(report.contains("inside macro (heuristic)"), "{}", report);
+}
+
+#[test]
+fn shadows_are_classified_by_their_value() {
+    let source = "
+fn other() -> u8 { 1 }
+
+fn f(x: u8, y: u8, z: String) {
+    let x = x + 1;
+    let y = other();
+    let z = z.clone();
+    drop((x, y, z));
+}
+";
+    assert_eq!(
+        short("classes", source, &[]),
+        [
+            "note[derived-shadow]: `x` shadows the binding at 4:6",
+            "warning[unrelated-shadow]: `y` shadows the binding at 4:13 (shadowed value never used)",
+            "note[conversion-shadow]: `z` shadows the binding at 4:20",
+        ]
+    );
+    assert_eq!(
+        short("classes-unrelated", source, &["--class", "unrelated"]),
+        ["warning[unrelated-shadow]: `y` shadows the binding at 4:13 (shadowed value never used)"]
+    );
+}
+
+#[test]
+fn rebinding_a_variable_to_itself_is_only_reported_with_report_rebinds() {
+    let source = "
+fn f() {
+    let mut x = 1;
+    x += 1;
+    let x = x;
+    drop(x);
+}
+";
+    assert_eq!(short("rebinds-default", source, &[]), Vec::<String>::new());
+    assert_eq!(
+        short("rebinds", source, &["--report-rebinds"]),
+        ["note[derived-shadow]: `x` shadows the binding at 3:13 (mut→immut)"]
+    );
+}
+
+#[test]
+fn changes_of_mutability_are_noted() {
+    let source = "
+fn f(buf: Vec<u8>) {
+    let mut buf = buf.into_iter().collect::<Vec<u8>>();
+    buf.push(1);
+    let buf = buf.len();
+    drop(buf);
+}
+";
+    assert_eq!(
+        short("mutability", source, &[]),
+        [
+            "note[derived-shadow]: `buf` shadows the binding at 2:6 (immut→mut)",
+            "note[derived-shadow]: `buf` shadows the binding at 3:13 (mut→immut)",
+        ]
+    );
+}
+
+#[test]
+fn apparent_type_changes_are_warnings() {
+    let source = "
+fn f() {
+    let n = 1u8;
+    let n = n as u32;
+    let s = String::new();
+    let s = s.clone();
+    drop((n, s));
+}
+";
+    assert_eq!(
+        short("type-changes", source, &[]),
+        [
+            "warning[derived-shadow]: `n` shadows the binding at 3:9 (apparent type u8→u32)",
+            "note[conversion-shadow]: `s` shadows the binding at 5:9",
+        ]
+    );
+}
+
+#[test]
+fn shadows_of_the_loop_variable_in_its_body_are_noted() {
+    let source = "
+fn f(items: Vec<String>) {
+    for item in items {
+        let item = item.trim().to_string();
+        drop(item);
+    }
+}
+";
+    assert_eq!(
+        short("loop-variable", source, &[]),
+        ["note[derived-shadow]: `item` shadows the binding at 3:9 (shadows loop variable)"]
+    );
+}
+
+#[test]
+fn ignore_underscore_leaves_out_names_starting_with_an_underscore() {
+    let source = "
+fn f() {
+    let _guard = 1;
+    let _guard = 2;
+    let x = 1;
+    let x = x + 1;
+    drop(x);
+}
+";
+    assert_eq!(short("underscore-default", source, &[]).len(), 2);
+    assert_eq!(
+        short("underscore", source, &["--ignore-underscore"]),
+        ["note[derived-shadow]: `x` shadows the binding at 5:9"]
+    );
+}
+
+/// A function with a shadow, and a test module with another one.
+const WITH_TESTS: &str = "
+fn f(x: u8) {
+    let x = x + 1;
+    drop(x);
+}
+
+#[cfg(test)]
+mod tests {
+    fn helper(y: u8) {
+        let y = y + 1;
+        drop(y);
+    }
+}
+";
+
+#[test]
+fn tests_can_be_skipped_or_checked_alone() {
+    assert_eq!(
+        short("skip-tests", WITH_TESTS, &["--skip-tests"]),
+        ["note[derived-shadow]: `x` shadows the binding at 2:6"]
+    );
+    assert_eq!(
+        short("only-tests", WITH_TESTS, &["--only-tests"]),
+        ["note[derived-shadow]: `y` shadows the binding at 9:15"]
+    );
+}
+
+#[test]
+fn min_depth_only_reports_variables_shadowed_that_many_times() {
+    let source = "
+fn f() {
+    let x = 1;
+    let x = x + 1;
+    let y = 1;
+    let y = y + 1;
+    let y = y + 1;
+    drop((x, y));
+}
+";
+    assert_eq!(
+        short("min-depth", source, &["--min-depth", "2"]),
+        [
+            "note[derived-shadow]: `y` shadows the binding at 5:9",
+            "note[derived-shadow]: `y` shadows the binding at 6:9",
+        ]
+    );
+}
+
+#[test]
+fn min_distance_only_reports_shadows_far_from_what_they_shadow() {
+    let source = "
+fn f() {
+    let x = 1;
+    let x = x + 1;
+    let y = 1;
+
+
+
+    let y = y + 1;
+    drop((x, y));
+}
+";
+    assert_eq!(
+        short("min-distance", source, &["--min-distance", "3"]),
+        ["note[derived-shadow]: `y` shadows the binding at 5:9"]
+    );
+}
+
+#[test]
+fn locations_have_columns() {
+    let source = "
+fn f() {
+    let x = 1; let x = x + 1; drop(x);
+}
+";
+    let output = light("columns", source, &["--format", "quickfix"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.trim(), "lib.rs:3:20: `x` shadows the binding at 3:9");
+}
+
+#[test]
+fn distances_are_measured_from_the_end_of_the_shadowed_statement() {
+    let source = "
+fn f() {
+    let x = (1,
+        2);
+    let x = x.0 + 1;
+    drop(x);
+}
+";
+    let output = light("statements", source, &["--format-template", "{distance}"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.trim(), "1");
+}
+
+#[test]
+fn shadows_of_values_that_are_never_used_are_noted() {
+    let source = "
+fn expensive() -> u8 { 1 }
+
+fn f() {
+    let x = expensive();
+    let x = 2;
+    let y = expensive();
+    drop(y);
+    let y = 2;
+    drop((x, y));
+}
+";
+    assert_eq!(
+        short("never-used", source, &[]),
+        [
+            "warning[unrelated-shadow]: `x` shadows the binding at 5:9 (shadowed value never used)",
+            "warning[unrelated-shadow]: `y` shadows the binding at 7:9",
+        ]
+    );
+}
+
+#[test]
+fn shadows_of_closure_captures_are_noted() {
+    let source = "
+fn make_other() -> u8 { 1 }
+
+fn f() {
+    let tx = 1;
+    let g = move || {
+        let tx = make_other();
+        drop(tx);
+    };
+    drop((tx, g));
+}
+";
+    assert_eq!(
+        short("captures", source, &[]),
+        ["warning[unrelated-shadow]: `tx` shadows the binding at 5:9 (shadows closure capture)"]
+    );
+}
+
+#[test]
+fn ref_bindings_are_only_reported_with_include_ref_patterns() {
+    let source = "
+fn f(y: u8) {
+    let x = 1;
+    let ref x = y;
+    drop((x, y));
+}
+";
+    assert_eq!(short("ref-default", source, &[]), Vec::<String>::new());
+    assert_eq!(
+        short("ref", source, &["--include-ref-patterns"]),
+        ["warning[unrelated-shadow]: `x` shadows the binding at 3:9 (shadowed value never used)"]
+    );
+}
+
+#[test]
+fn raw_identifiers_keep_their_prefix_and_match_each_other() {
+    let source = "
+fn f(r#type: u8, type_: u8) {
+    let r#type = r#type + 1;
+    let type_ = type_ + 1;
+    drop((r#type, type_));
+}
+";
+    assert_eq!(
+        short("raw", source, &[]),
+        [
+            "note[derived-shadow]: `r#type` shadows the binding at 2:6",
+            "note[derived-shadow]: `type_` shadows the binding at 2:18",
+        ]
+    );
+}
+
+#[test]
+fn shebangs_and_frontmatter_are_skipped_with_their_lines() {
+    let source = "#!/usr/bin/env -S cargo +nightly -Zscript
+---
+[dependencies]
+---
+#![allow(unused)]
+
+fn main() {
+    let x = 1;
+    let x = x + 1;
+    drop(x);
+}
+";
+    assert_eq!(
+        short("script", source, &[]),
+        ["note[derived-shadow]: `x` shadows the binding at 8:9"]
+    );
+}
+
+#[test]
+fn shadows_in_unsafe_code_are_errors() {
+    let source = "
+fn f(ptr: *const u8, len: usize) {
+    unsafe {
+        let len = len + 1;
+        drop((ptr, len));
+    }
+}
+";
+    assert_eq!(
+        short("unsafe", source, &[]),
+        ["error[derived-shadow]: `len` shadows the binding at 2:22 (in unsafe)"]
+    );
+}
+
+#[test]
+fn doctests_are_analyzed_at_their_lines_of_the_doc_comment() {
+    let source = "
+/// Adds one.
+///
+/// ```
+/// let x = 1;
+///     let x = x + 1;
+/// assert_eq!(x, 2);
+/// ```
+pub fn add_one() {}
+";
+    assert_eq!(short("doctests-default", source, &[]), Vec::<String>::new());
+    let output = light(
+        "doctests",
+        source,
+        &["--include-doctests", "--format", "quickfix"],
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.trim(), "lib.rs:6:13: `x` shadows the binding at 5:9");
+}
+
+#[test]
+fn test_functions_are_labeled_and_listed_after_the_others() {
+    let source = "
+#[test]
+fn checks() {
+    let x = 1;
+    let x = x + 1;
+    drop(x);
+}
+
+fn f(y: u8) {
+    let y = y + 1;
+    drop(y);
+}
+";
+    let report = report("test-bucket", source, &["--color", "never"]);
+    let f = report.find(" f ").unwrap();
+    let checks = report.find(" checks ").unwrap();
+    assert!(f < checks, "{}", report);
+    assert!(report.contains("checks          test"), "{}", report);
+    assert_eq!(
+        short("tests-only", source, &["--tests-only"]),
+        ["note[derived-shadow]: `x` shadows the binding at 4:9"]
+    );
+}
+
+#[test]
+fn narrowing_is_only_reported_with_strict() {
+    let source = "
+fn f(config: Option<u8>, x: Result<u8, ()>, y: Option<u8>) -> Result<(), ()> {
+    let config = config.unwrap();
+    let x = x?;
+    let y = y.expect(\"y\");
+    drop((config, x, y));
+    Ok(())
+}
+";
+    assert_eq!(short("narrowing", source, &[]), Vec::<String>::new());
+    assert_eq!(short("narrowing-strict", source, &["--strict"]).len(), 3);
+}
+
+#[test]
+fn ownership_conversions_are_a_class_of_their_own() {
+    let source = "
+fn f(s: String, n: &str, p: u8) {
+    let s = s.clone();
+    let n = n.to_owned();
+    let p = p + 1;
+    drop((s, n, p));
+}
+";
+    assert_eq!(
+        short("conversions", source, &["--class", "conversion"]),
+        [
+            "note[conversion-shadow]: `s` shadows the binding at 2:6",
+            "note[conversion-shadow]: `n` shadows the binding at 2:17",
+        ]
+    );
+
+    let dir = Scratch::new("conversions-config");
+    dir.write("lib.rs", source);
+    dir.write("cargo-light.toml", "ignore-conversions = true\n");
+    assert_eq!(
+        messages(dir.run(&["-F", "lib.rs", "--format", "short"])),
+        ["note[derived-shadow]: `p` shadows the binding at 2:26"]
+    );
+}
+
+#[test]
+fn names_reused_in_sibling_scopes_are_reported_with_sibling_reuse() {
+    let source = "
+fn f() {
+    {
+        let result = 1;
+        drop(result);
+    }
+    {
+        let result = 2;
+        drop(result);
+    }
+}
+";
+    assert_eq!(short("siblings-default", source, &[]), Vec::<String>::new());
+    assert_eq!(
+        short("siblings", source, &["--sibling-reuse"]),
+        ["note[reused-name]: `result` reuses the name of the binding at 4:13 in a sibling scope"]
+    );
+}
+
+#[test]
+fn included_and_path_module_files_are_analyzed_once() {
+    let dir = Scratch::new("includes");
+    dir.write(
+        "src/lib.rs",
+        "#[path = \"other/m.rs\"]\nmod m;\ninclude!(\"gen.rs\");\n",
+    );
+    dir.write(
+        "src/other/m.rs",
+        "fn g(x: u8) {\n    let x = x + 1;\n    drop(x);\n}\n",
+    );
+    dir.write(
+        "src/gen.rs",
+        "fn h(y: u8) {\n    let y = y + 1;\n    drop(y);\n}\n",
+    );
+    let expected = ["src/other/m.rs:2:9 m::g", "src/gen.rs:2:9 h"];
+    for args in [&["-F", "src/lib.rs"][..], &["-d", "src"]] {
+        let output = dir.run(
+            &[
+                args,
+                &["--format-template", "{file}:{line}:{col} {function}"],
+            ]
+            .concat(),
+        );
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout.lines().collect::<Vec<_>>(), expected, "{:?}", args);
+    }
+}
+
+#[test]
+fn functions_are_named_with_their_module_path() {
+    let source = "
+mod parser {
+    mod lexer {
+        fn next_token(x: u8) {
+            let x = x + 1;
+            drop(x);
+        }
+    }
+}
+";
+    assert_eq!(
+        report(
+            "modules",
+            source,
+            &["--format-template", "{module} {function}"]
+        ),
+        "parser::lexer parser::lexer::next_token\n"
+    );
+}
+
+#[test]
+fn methods_are_named_with_their_impl_type() {
+    let source = "
+struct Vec3;
+
+impl Vec3 {
+    fn len(&self, y: u8) {
+        let y = y + 1;
+        drop(y);
+    }
+}
+
+impl std::fmt::Display for Vec3 {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let f: &mut std::fmt::Formatter = &mut *f;
+        write!(f, \"\")
+    }
+}
+";
+    assert_eq!(
+        report("impls", source, &["--format-template", "{function}"]),
+        "Vec3::len\n<Vec3 as Display>::fmt\n"
+    );
+}
+
+#[test]
+fn locals_that_hide_fields_of_self_are_reported_with_check_fields() {
+    let source = "
+struct Buffer {
+    data: Vec<u8>,
+}
+
+impl Buffer {
+    fn len(&self) -> usize {
+        let data = &self.data;
+        data.len()
+    }
+}
+";
+    assert_eq!(short("fields-default", source, &[]), Vec::<String>::new());
+    assert_eq!(
+        short("fields", source, &["--check-fields"]),
+        ["warning[item-shadow]: `data` shadows the field declared at 3:5"]
+    );
+}
+
+#[test]
+fn closure_arguments_that_hide_arguments_and_locals_are_noted() {
+    let source = "
+fn handle(req: u8, items: Vec<u8>) {
+    let total = 0;
+    items.iter().map(|req| req + 1).for_each(|total| drop(total));
+    drop((req, total));
+}
+";
+    assert_eq!(
+        short("closures", source, &[]),
+        [
+            "warning[unrelated-shadow]: `req` shadows the binding at 2:11 (closure argument shadows function argument)",
+            "warning[unrelated-shadow]: `total` shadows the binding at 3:9 (closure argument shadows local)",
+        ]
+    );
+}
+
+#[test]
+fn loops_over_a_collection_of_the_same_name_are_noted() {
+    let source = "
+fn f(items: Vec<Vec<u8>>) {
+    for items in items {
+        drop(items);
+    }
+}
+";
+    assert_eq!(
+        short("for-in", source, &[]),
+        ["note[derived-shadow]: `items` shadows the binding at 2:6 (shadows iterated collection)"]
+    );
+}
+
+#[test]
+fn chains_show_the_kind_of_each_initializer() {
+    let source = "
+fn f(y: u8) -> Option<u8> {
+    let x = 10;
+    let x = x.checked_add(y)?;
+    let x = std::convert::identity(x);
+    Some(x)
+}
+";
+    let human = report("chain", source, &["--color", "never"]);
+    assert!(
+        human.contains("x                     3 @ 3:9 (literal) → 4:9 (?) → 5:9 (function call)\n"),
+        "{}",
+        human
+    );
+}
+
+#[test]
+fn bindings_are_shown_with_their_statement() {
+    let source = "
+fn f(path: &str) -> usize {
+    let config = path.len();
+    let config = match config { 0 => 1, n => n };
+    config
+}
+";
+    let human = report("snippet", source, &["--color", "never"]);
+    assert!(
+        human.contains("3:9   let config = path.len()   (original)\n"),
+        "{}",
+        human
+    );
+    assert!(
+        human.contains("4:9   let config = match config { 0 => 1, n => n }   (in fn f)\n"),
+        "{}",
+        human
+    );
+    let json = report("snippet-json", source, &["--format", "json"]);
+    assert!(
+        json.contains("\"snippet\": \"let config = path.len()\""),
+        "{}",
+        json
+    );
+}
+
+#[test]
+fn shadows_are_shown_with_the_scopes_they_are_in() {
+    let source = "
+fn f(flag: bool) {
+    let x = 1;
+    if flag {
+        let q = || {
+            let x = 2;
+            x
+        };
+        drop(q);
+    }
+    drop(x);
+}
+";
+    assert_eq!(
+        report("scopes", source, &["--format-template", "{line}:{col}"]),
+        "6:17\n"
+    );
+    let human = report("scopes-human", source, &["--color", "never"]);
+    assert!(
+        human.contains("let x = 2   (in fn f > if > closure)\n"),
+        "{}",
+        human
+    );
+}
+
+#[test]
+fn destructured_arguments_are_bindings() {
+    let source = "
+struct Point {
+    x: u8,
+}
+
+fn dist((x1, y1): (u8, u8), Point { x: x2 }: Point) {
+    let x1 = x1 + 1;
+    let x2 = x2 + y1;
+    drop((x1, x2));
+}
+";
+    assert_eq!(
+        short("destructured-arguments", source, &[]),
+        [
+            "note[derived-shadow]: `x1` shadows the binding at 6:10",
+            "note[derived-shadow]: `x2` shadows the binding at 6:40",
+        ]
+    );
+}
+
+#[test]
+fn functions_are_reported_with_their_shadow_density() {
+    let source = "
+fn long(x: u8) {
+    let x = x + 1;
+    drop(x);
+    drop(1);
+    drop(1);
+    drop(1);
+    drop(1);
+    drop(1);
+    drop(1);
+    drop(1);
+}
+
+fn short(y: u8) {
+    let y = y + 1;
+    drop(y);
+}
+";
+    let human = report("density", source, &["--color", "never"]);
+    assert!(human.contains("(9.1 shadows per 100 lines)"), "{}", human);
+    assert!(human.contains("(25.0 shadows per 100 lines)"), "{}", human);
+    let args = ["--format-template", "{function}", "--min-density", "20"];
+    assert_eq!(report("min-density", source, &args), "short\n");
+}
+
+#[test]
+fn functions_are_reported_with_their_lines() {
+    let source = "
+fn f(x: u8) {
+    let x = x + 1;
+    drop(x);
+}
+";
+    let human = report("loc", source, &["--color", "never"]);
+    assert!(
+        human.contains(" f               lines 2–5 (4 LOC) "),
+        "{}",
+        human
+    );
+}
+
+#[test]
+fn const_initializers_and_blocks_are_analyzed() {
+    let source = "
+const FOO: u8 = {
+    let x = 1;
+    let x = x + 1;
+    x
+};
+
+struct S;
+
+impl S {
+    const BAR: u8 = {
+        let y = 1;
+        let y = y + 1;
+        y
+    };
+}
+
+fn f() -> u8 {
+    const {
+        let z = 1;
+        let z = z + 1;
+        z
+    }
+}
+";
+    assert_eq!(
+        report(
+            "consts",
+            source,
+            &["--format-template", "{function} {line}:{col}"]
+        ),
+        "FOO 4:9\nS::BAR 13:13\nf 21:13\n"
+    );
+}
+
+#[test]
+fn names_bound_twice_in_a_macro_arm_are_reported_with_macro_rules() {
+    let source = "
+macro_rules! m {
+    ($e:expr) => {
+        let v = $e;
+        let v = v + 1;
+    };
+}
+";
+    assert_eq!(
+        short("macro-rules-default", source, &[]),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        short("macro-rules", source, &["--macro-rules"]),
+        ["note[derived-shadow]: `v` shadows the binding at 4:13"]
+    );
+    let human = report(
+        "macro-rules-human",
+        source,
+        &["--color", "never", "--macro-rules"],
+    );
+    assert!(
+        human.contains("potential shadows in macro definition"),
+        "{}",
+        human
+    );
+    assert!(human.contains("(in macro_rules! m > arm 1)"), "{}", human);
+}
+
+#[test]
+fn variables_are_listed_by_the_line_of_their_first_binding() {
+    let source = "
+fn f(b: u8) {
+    let d = 1;
+    let a = 2;
+    let c = 3;
+    let c = c + 1;
+    let a = a + 1;
+    let d = d + 1;
+    let b = b + 1;
+    drop((a, b, c, d));
+}
+";
+    let args = ["--format-template", "{var} {line}"];
+    let expected = "b 9\nd 8\na 7\nc 6\n";
+    for _ in 0..5 {
+        assert_eq!(report("order", source, &args), expected);
+    }
+}
+
+#[test]
+fn functions_of_the_same_name_are_told_apart() {
+    let source = "
+struct A;
+struct B;
+
+impl A {
+    fn new(x: u8) {
+        let x = x + 1;
+        drop(x);
+    }
+}
+
+impl B {
+    fn new(x: u8) {
+        let x = x + 1;
+        drop(x);
+    }
+}
+
+mod m {
+    fn new(x: u8) {
+        let x = x + 1;
+        drop(x);
+    }
+}
+";
+    assert_eq!(
+        report("same-names", source, &["--format-template", "{function}"]),
+        "A::new\nB::new\nm::new\n"
+    );
+}
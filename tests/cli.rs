@@ -0,0 +1,858 @@
+//! Runs `cargo-light` on small sources and checks what it reports.
+
+mod common;
+
+use common::{light, messages, quickfix, report, short, Scratch};
+
+#[test]
+fn variants_in_match_arms_are_not_bindings() {
+    let source = "
+fn f(x: Option<Option<u8>>, y: Option<u8>) {
+    match x {
+        Some(None) => match y {
+            None => {}
+            Some(v) => drop(v),
+        },
+        None => {}
+        Some(Some(v)) => drop(v),
+    }
+    if let None = y {}
+}
+";
+    assert_eq!(
+        quickfix("prelude-variants", source, &[]),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn constants_in_match_arms_are_not_bindings() {
+    let source = "
+const MAX: u8 = 9;
+#[allow(non_upper_case_globals)]
+static min: u8 = 0;
+
+fn f(x: u8) {
+    match x {
+        MAX => {}
+        min => {}
+        _ => {}
+    }
+}
+";
+    let found = quickfix("const-patterns", source, &["--check-items"]);
+    assert_eq!(found, Vec::<String>::new());
+}
+
+#[test]
+fn variants_and_unit_structs_in_patterns_are_not_bindings() {
+    let source = "
+#[allow(non_camel_case_types)]
+pub enum Direction { north, South }
+use Direction::*;
+#[allow(non_camel_case_types)]
+pub struct marker;
+
+fn f(d: Direction, m: marker) {
+    match d {
+        north => {}
+        South => {}
+    }
+    if let marker = m {}
+}
+";
+    assert_eq!(quickfix("unit-patterns", source, &[]), Vec::<String>::new());
+}
+
+#[test]
+fn default_methods_have_the_cfgs_of_their_trait() {
+    let source = "
+#[cfg(windows)]
+trait T {
+    fn f(&self) {
+        let x = 1;
+        let x = x + 1;
+        drop(x);
+    }
+}
+";
+    let output = light("trait-cfgs", source, &["--format", "jsonl"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(r#""cfgs":["windows"]"#), "{}", stdout);
+}
+
+#[test]
+fn pipes_are_escaped_in_markdown_tables() {
+    let source = "
+fn f(x: u8) {
+    let x = x | 1;
+    drop(x);
+}
+";
+    let output = light(
+        "pipe|cell",
+        source,
+        &["--format", "markdown", "--absolute-paths"],
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let row = stdout.lines().find(|line| line.contains("lib.rs")).unwrap();
+    assert!(row.contains(r"pipe\|cell"), "{}", row);
+    assert_eq!(row.matches(" | ").count(), 4, "{}", row);
+}
+
+#[test]
+fn a_name_hiding_an_item_twice_is_one_variable() {
+    let source = "
+fn parse() {}
+
+fn f() {
+    let parse = 1;
+    let parse = parse + 1;
+    drop(parse);
+}
+";
+    let output = light("item-names", source, &["--format", "json"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(r#""shadowed_variables": 1"#), "{}", stdout);
+}
+
+/// Two locals in one function that hide the same item.
+const TWO_ITEM_SHADOWS: &str = "
+fn parse() {}
+
+fn f() {
+    let parse = 1;
+    let parse = 2;
+    drop(parse);
+}
+";
+
+#[test]
+fn item_shadows_of_one_name_have_their_own_fingerprints() {
+    let output = light(
+        "item-fingerprints",
+        TWO_ITEM_SHADOWS,
+        &["--format", "sarif"],
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let fingerprints: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.contains("shadow/v1"))
+        .collect();
+    assert_eq!(fingerprints.len(), 3, "{}", stdout);
+    for (i, fingerprint) in fingerprints.iter().enumerate() {
+        assert!(!fingerprints[..i].contains(fingerprint), "{}", stdout);
+    }
+}
+
+#[test]
+fn baseline_leaves_out_item_shadows_of_one_name() {
+    let dir = Scratch::new("item-baseline");
+    dir.write("lib.rs", TWO_ITEM_SHADOWS);
+    let saved = dir.run(&["-F", "lib.rs", "--save-baseline", "light.baseline"]);
+    assert!(saved.status.success());
+    let found = messages(dir.run(&[
+        "-F",
+        "lib.rs",
+        "--format",
+        "quickfix",
+        "--baseline",
+        "light.baseline",
+    ]));
+    assert_eq!(found, Vec::<String>::new());
+}
+
+#[test]
+fn min_severity_leaves_out_item_shadows_of_one_name() {
+    let kept = quickfix(
+        "item-severity",
+        TWO_ITEM_SHADOWS,
+        &["--min-severity", "warning"],
+    );
+    assert_eq!(kept.len(), 3, "{:?}", kept);
+    let found = quickfix(
+        "item-severity",
+        TWO_ITEM_SHADOWS,
+        &["--min-severity", "error"],
+    );
+    assert_eq!(found, Vec::<String>::new());
+}
+
+#[test]
+fn exclude_path_leaves_out_matching_files_and_directories() {
+    let shadow = "fn f(x: u8) { let x = x + 1; drop(x); }\n";
+    let dir = Scratch::new("exclude-path");
+    dir.write("lib.rs", shadow);
+    dir.write("generated/gen.rs", shadow);
+    dir.write("x_snapshot.rs", shadow);
+    let output = dir.run(&[
+        "--format",
+        "quickfix",
+        "--exclude-path",
+        "generated",
+        "--exclude-path",
+        "*_snapshot.rs",
+    ]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let files: Vec<&str> = stdout
+        .lines()
+        .map(|line| line.split(':').next().unwrap())
+        .collect();
+    assert_eq!(files, ["lib.rs"], "{}", stdout);
+}
+
+#[test]
+fn exclude_leaves_out_paths_without_workspace() {
+    let shadow = "fn f(x: u8) { let x = x + 1; drop(x); }\n";
+    let dir = Scratch::new("exclude");
+    dir.write("lib.rs", shadow);
+    dir.write("src/generated/gen.rs", shadow);
+    let output = dir.run(&["--format", "quickfix", "--exclude", "src/generated"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let files: Vec<&str> = stdout
+        .lines()
+        .map(|line| line.split(':').next().unwrap())
+        .collect();
+    assert_eq!(files, ["lib.rs"], "{}", stdout);
+}
+
+#[test]
+fn awaits_of_async_blocks_are_not_across_await() {
+    let source = "
+async fn g() {}
+
+fn f() {
+    let x = 1;
+    let _block = async { g().await };
+    let _closure = async || g().await;
+    let x = x + 2;
+    drop(x);
+}
+";
+    assert_eq!(
+        short("async-blocks", source, &[]),
+        ["note[derived-shadow]: `x` shadows the binding at 5:9"]
+    );
+}
+
+#[test]
+fn shadows_across_await_have_a_rule_of_their_own() {
+    let source = "
+async fn g() {}
+
+async fn f() {
+    let x = 1;
+    g().await;
+    let x = x + 2;
+    drop(x);
+}
+";
+    assert_eq!(
+        short("across-await", source, &[]),
+        ["error[across-await]: `x` shadows the binding at 5:9 (across .await)"]
+    );
+}
+
+#[test]
+fn match_arms_that_rebind_the_scrutinee_have_a_rule_of_their_own() {
+    let source = "
+fn f(result: Option<u8>) {
+    match result {
+        result => drop(result),
+    }
+}
+";
+    assert_eq!(
+        short("rebinds-scrutinee", source, &[]),
+        ["error[rebinds-scrutinee]: `result` shadows the binding at 2:6 (rebinds match scrutinee)"]
+    );
+    let output = light("rebinds-scrutinee-sarif", source, &["--format", "sarif"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains(r#""id": "rebinds-scrutinee""#),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(r#""ruleId": "rebinds-scrutinee""#),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn locals_that_hide_a_function_have_a_rule_of_their_own() {
+    let source = "
+const LIMIT: u8 = 1;
+
+fn parse() {}
+
+fn f() {
+    let parse = 1;
+    #[allow(non_snake_case)]
+    let LIMIT = 2;
+    drop((parse, LIMIT));
+}
+";
+    assert_eq!(
+        short("fn-shadow", source, &["--check-items"]),
+        [
+            "warning[fn-shadow]: `parse` shadows the fn declared at 4:4",
+            "warning[item-shadow]: `LIMIT` shadows the const declared at 2:7",
+        ]
+    );
+}
+
+#[test]
+fn expand_only_writes_the_human_readable_report() {
+    for args in [
+        &["--format", "sarif"][..],
+        &["--emit", "json=report.json"],
+        &["--format-template", "{file}"],
+    ] {
+        let output = light("expand-format", "", &[&["--expand"], args].concat());
+        assert!(!output.status.success(), "{:?}", args);
+    }
+}
+
+#[test]
+fn chains_have_the_notes_of_the_bindings_they_show() {
+    let source = "fn f(y: u8) { let y = y; let y = 3; drop(y); }\n";
+    let output = light("allowed-chain", source, &["--color", "never"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let chain = stdout.lines().find(|line| line.contains(" @ ")).unwrap();
+    assert!(
+        chain.ends_with("1:6 (argument) → 1:30 (literal)"),
+        "{}",
+        chain
+    );
+}
+
+#[test]
+fn type_changes_are_apparent() {
+    let source = "
+fn f() {
+    let x = String::from(\"a\");
+    let x = x.len();
+    drop(x);
+}
+";
+    assert_eq!(
+        short("apparent-type", source, &[]),
+        ["warning[derived-shadow]: `x` shadows the binding at 3:9 (apparent type String→usize)"]
+    );
+    let output = light("apparent-type-json", source, &["--format", "jsonl"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains(r#""id":"changes-apparent-type""#),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn version_is_the_one_of_the_package() {
+    let dir = Scratch::new("version");
+    for args in [&["--version"][..], &["light", "--version"]] {
+        let output = dir.run(args);
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(
+            stdout.trim(),
+            concat!("cargo-light ", env!("CARGO_PKG_VERSION"))
+        );
+    }
+}
+
+/// A `cargo-expand` that expands the lib target to its source, and fails for
+/// every other one.
+#[cfg(unix)]
+const CARGO_EXPAND: &str = "#!/bin/sh
+case \"$*\" in
+*--lib*) cat src/lib.rs ;;
+*) echo 'cannot expand' >&2; exit 1 ;;
+esac
+";
+
+#[cfg(unix)]
+#[test]
+fn expand_goes_on_past_targets_that_fail_to_expand() {
+    use std::env;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = Scratch::new("expand");
+    dir.write(
+        "Cargo.toml",
+        "[package]\nname = \"ex\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    );
+    dir.write(
+        "src/lib.rs",
+        "pub fn f(x: u8) {\n    let x = x + 1;\n    drop(x);\n}\n",
+    );
+    dir.write("src/main.rs", "fn main() {}\n");
+    dir.write("bin/cargo-expand", CARGO_EXPAND);
+    let script = dir.path().join("bin/cargo-expand");
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = env::join_paths(
+        std::iter::once(dir.path().join("bin"))
+            .chain(env::split_paths(&env::var_os("PATH").unwrap())),
+    )
+    .unwrap();
+    let output = dir
+        .command(&["--expand", "--color", "never"])
+        .env("PATH", path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stdout.contains("(expanded from src/lib.rs:1:8)"),
+        "{}",
+        stdout
+    );
+    assert!(stderr.contains("Unable to expand"), "{}", stderr);
+    assert!(stderr.contains("cannot expand"), "{}", stderr);
+}
+
+#[test]
+fn summary_goes_to_stderr_for_other_formats() {
+    let source = "
+fn f(x: u8) {
+    let x = x + 1;
+    let x = x + 1;
+    drop(x);
+}
+";
+    let summary = "Summary: scanned 1 file in ";
+    let human = light("summary", source, &["--color", "never"]);
+    let stdout = String::from_utf8(human.stdout).unwrap();
+    assert!(stdout.contains(summary), "{}", stdout);
+    assert!(
+        stdout.contains("  shadows:                2\n"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("  most shadowed:          x (2)\n"),
+        "{}",
+        stdout
+    );
+
+    let json = light("summary-json", source, &["--format", "json"]);
+    assert!(!String::from_utf8(json.stdout).unwrap().contains(summary));
+    let stderr = String::from_utf8(json.stderr).unwrap();
+    assert!(stderr.contains(summary), "{}", stderr);
+}
+
+/// `b` with two shadows, `a` with one, and a function without any.
+const TWO_FUNCTIONS: &str = "
+fn b(x: u8) {
+    let x = x + 1;
+    let x = x + 1;
+    drop(x);
+}
+
+fn a(y: u8) {
+    let y = y + 1;
+    drop(y);
+    drop(1);
+    drop(1);
+    drop(1);
+    drop(1);
+    drop(1);
+}
+
+fn clean() {}
+";
+
+#[test]
+fn sort_orders_the_functions() {
+    let functions = |name, sort| {
+        let args = ["--format-template", "{function}", "--sort", sort];
+        let names = report(name, TWO_FUNCTIONS, &args);
+        let mut names: Vec<String> = names.lines().map(String::from).collect();
+        names.dedup();
+        names
+    };
+    assert_eq!(functions("sort-line", "line"), ["b", "a"]);
+    assert_eq!(functions("sort-name", "name"), ["a", "b"]);
+    assert_eq!(functions("sort-count", "count"), ["b", "a"]);
+    assert_eq!(functions("sort-density", "density"), ["b", "a"]);
+}
+
+#[test]
+fn group_by_lists_the_files_by_module_and_crate() {
+    let dir = Scratch::new("group-by");
+    dir.write(
+        "Cargo.toml",
+        "[package]\nname = \"w\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    );
+    dir.write(
+        "src/lib.rs",
+        "mod m;\n\nfn f(x: u8) {\n    let x = x + 1;\n    drop(x);\n}\n",
+    );
+    dir.write(
+        "src/m.rs",
+        "fn g(y: u8) {\n    let y = y + 1;\n    drop(y);\n}\n",
+    );
+    let group = |by| {
+        let output = dir.run(&["--color", "never", "--group-by", by]);
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        stdout
+            .lines()
+            .filter(|line| line.starts_with("module ") || line.starts_with("crate "))
+            .map(String::from)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        group("module"),
+        [
+            "module w: 1 shadow in 1 function",
+            "module w::m: 1 shadow in 1 function",
+        ]
+    );
+    assert_eq!(group("crate"), ["crate w: 2 shadows in 2 functions"]);
+}
+
+#[test]
+fn quiet_reports_shadows_through_the_exit_code() {
+    let source = "
+fn f(x: u8) {
+    let x = x + 1;
+    drop(x);
+}
+";
+    let quiet = light("quiet", source, &["-q"]);
+    assert_eq!(quiet.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(quiet.stdout).unwrap(),
+        "cargo light: 1 shadow of 1 variable in 1 function of 1 file (1 scanned)\n"
+    );
+    let silent = light("quiet-twice", source, &["-qq"]);
+    assert_eq!(silent.status.code(), Some(1));
+    assert!(silent.stdout.is_empty() && silent.stderr.is_empty());
+    let clean = light("quiet-clean", "fn f() {}\n", &["-q"]);
+    assert_eq!(clean.status.code(), Some(0));
+}
+
+#[test]
+fn verbose_also_lists_functions_without_shadows() {
+    let source = "
+fn f(x: u8) {
+    let x = x + 1;
+    drop(x);
+}
+
+fn clean() {}
+";
+    let args = ["--color", "never"];
+    let human = report("verbose-default", source, &args);
+    assert!(!human.contains("clean"), "{}", human);
+    let verbose = report("verbose", source, &[&args[..], &["-v"]].concat());
+    assert!(verbose.contains("  ✓ line:     7:4 clean\n"), "{}", verbose);
+    let none = report("verbose-clean", "fn g() {}\n", &["--color", "never", "-v"]);
+    assert!(
+        none.starts_with("lib.rs has no shadowed variables:\n"),
+        "{}",
+        none
+    );
+}
+
+#[test]
+fn color_is_only_written_when_asked_for_or_to_a_terminal() {
+    let source = "
+fn f(x: u8) {
+    let x = x + 1;
+    drop(x);
+}
+";
+    let colored = |name, args: &[&str]| report(name, source, args).contains("\x1b[");
+    assert!(colored("color-always", &["--color", "always"]));
+    assert!(!colored("color-never", &["--color", "never"]));
+    assert!(!colored("color-auto", &[]));
+
+    let dir = Scratch::new("no-color");
+    dir.write("lib.rs", source);
+    let output = dir
+        .command(&["-F", "lib.rs", "--color", "auto"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(!String::from_utf8(output.stdout).unwrap().contains("\x1b["));
+}
+
+#[test]
+fn hyperlinks_link_locations_to_the_link_template() {
+    let source = "
+fn f(x: u8) {
+    let x = x + 1;
+    drop(x);
+}
+";
+    let args = ["--color", "never", "--hyperlinks", "always"];
+    let linked = report("hyperlinks", source, &args);
+    assert!(linked.contains("\x1b]8;;file:///"), "{:?}", linked);
+    assert!(
+        linked.contains("/lib.rs\x1b\\2:6\x1b]8;;\x1b\\"),
+        "{:?}",
+        linked
+    );
+
+    let template = ["--link-template", "vscode://file{path}:{line}:{col}"];
+    let linked = report("link-template", source, &[&args[..], &template].concat());
+    assert!(
+        linked.contains("/lib.rs:2:6\x1b\\2:6\x1b]8;;\x1b\\"),
+        "{:?}",
+        linked
+    );
+
+    let plain = report("hyperlinks-auto", source, &["--color", "never"]);
+    assert!(!plain.contains("\x1b]8;;"), "{:?}", plain);
+}
+
+#[test]
+fn original_bindings_are_shown_above_their_shadows() {
+    let source = "
+fn f(path: &str) -> usize {
+    let conn = path.len();
+    drop(1);
+    let conn = conn + 1;
+    conn
+}
+";
+    let human = report("original-source", source, &["--color", "never"]);
+    let expected = "
+                        3:9   let conn = path.len()   (original)
+                        5:9   let conn = conn + 1   (in fn f)
+";
+    assert!(human.contains(expected), "{}", human);
+}
+
+#[test]
+fn paths_are_relative_to_the_workspace_unless_absolute_paths() {
+    let dir = Scratch::new("paths");
+    dir.write(
+        "Cargo.toml",
+        "[package]\nname = \"w\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    );
+    dir.write(
+        "src/lib.rs",
+        "fn f(x: u8) {\n    let x = x + 1;\n    drop(x);\n}\n",
+    );
+    let expected = ["src/lib.rs:2:9"];
+    let template = ["--format-template", "{file}:{line}:{col}"];
+    let runs: &[(&str, &[&str])] = &[
+        (".", &["-F", "src/lib.rs"]),
+        (".", &["-d", "src"]),
+        ("src", &["-F", "lib.rs"]),
+        ("src", &["-F", "../src/lib.rs"]),
+    ];
+    for (cwd, args) in runs {
+        let output = dir
+            .command(&[args, &template[..]].concat())
+            .current_dir(dir.path().join(cwd))
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout.lines().collect::<Vec<_>>(), expected, "{:?}", args);
+    }
+
+    let absolute = dir.run(&[&["-F", "src/lib.rs", "--absolute-paths"], &template[..]].concat());
+    let stdout = String::from_utf8(absolute.stdout).unwrap();
+    let file = stdout.trim().trim_end_matches(":2:9");
+    assert!(std::path::Path::new(file).is_absolute(), "{}", stdout);
+    assert!(file.ends_with("lib.rs"), "{}", stdout);
+}
+
+#[test]
+fn top_only_lists_the_functions_with_the_most_shadows() {
+    let args = ["--format-template", "{function}", "--top", "1"];
+    assert_eq!(report("top", TWO_FUNCTIONS, &args), "b\nb\n");
+    let human = report(
+        "top-human",
+        TWO_FUNCTIONS,
+        &["--color", "never", "--top", "1"],
+    );
+    assert!(human.contains(" b "), "{}", human);
+    assert!(!human.contains(" a "), "{}", human);
+}
+
+#[test]
+fn histogram_counts_the_functions_by_their_shadows() {
+    let human = report(
+        "histogram",
+        TWO_FUNCTIONS,
+        &["--color", "never", "--histogram"],
+    );
+    let histogram = &human[human.find("Shadows per function:\n").unwrap()..];
+    let counts: Vec<&str> = histogram
+        .lines()
+        .skip(1)
+        .map(|line| line.rsplit(' ').next().unwrap())
+        .collect();
+    assert_eq!(counts, ["1", "1", "1", "0", "0"], "{}", histogram);
+}
+
+#[test]
+fn long_names_are_cut_off_to_the_width_given() {
+    let source = "
+fn f(remaining_capacity_estimate: u8) {
+    let remaining_capacity_estimate = remaining_capacity_estimate + 1;
+    drop(remaining_capacity_estimate);
+}
+";
+    let dir = Scratch::new("name-width");
+    dir.write("lib.rs", source);
+    let name = |args: &[&str], columns| {
+        let output = dir
+            .command(&[&["-F", "lib.rs", "--color", "never"], args].concat())
+            .env("COLUMNS", columns)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let line = stdout.lines().nth(3).unwrap().trim_start().to_string();
+        line[..line.find(' ').unwrap()].to_string()
+    };
+    assert_eq!(name(&[], "200"), "remaining_capacity_estimate");
+    assert_eq!(name(&[], "40"), "remaining_capa…");
+    assert_eq!(name(&["--max-name-width", "10"], "200"), "remaining…");
+}
+
+#[test]
+fn output_and_emit_write_reports_to_files() {
+    let dir = Scratch::new("emit");
+    dir.write(
+        "lib.rs",
+        "fn f(x: u8) {\n    let x = x + 1;\n    drop(x);\n}\n",
+    );
+    let args = [
+        "-F",
+        "lib.rs",
+        "--format",
+        "short",
+        "-o",
+        "report.txt",
+        "--emit",
+        "json=report.json",
+        "--emit",
+        "csv=report.csv",
+    ];
+    let output = dir.run(&args);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    let read = |file| std::fs::read_to_string(dir.path().join(file)).unwrap();
+    assert_eq!(
+        read("report.txt"),
+        "lib.rs:2:9: note[derived-shadow]: `x` shadows the binding at 1:6\n"
+    );
+    assert!(read("report.json").contains("\"version\": 2"));
+    assert!(read("report.csv").starts_with("file,module,function,"));
+
+    let unknown = dir.run(&["-F", "lib.rs", "--emit", "bogus=report"]);
+    assert!(!unknown.status.success());
+    assert!(String::from_utf8(unknown.stderr)
+        .unwrap()
+        .contains("unknown format: bogus"));
+}
+
+#[test]
+fn config_files_set_options_and_the_theme() {
+    let source = "fn f(x: u8, y: u8) {\n    let x = x + 1;\n    let x = x + 1;\n    let y = y + 1;\n    drop((x, y));\n}\n";
+    let dir = Scratch::new("config");
+    dir.write("lib.rs", source);
+    let run = |args: &[&str]| dir.run(&[&["-F", "lib.rs"], args].concat());
+
+    dir.write("cargo-light.toml", "min-depth = 2\n");
+    let lines = messages(run(&["--format", "short"]));
+    assert_eq!(lines.len(), 2, "{:?}", lines);
+    let lines = messages(run(&["--format", "short", "--min-depth", "1"]));
+    assert_eq!(lines.len(), 3, "{:?}", lines);
+
+    dir.write("cargo-light.toml", "[theme]\noriginal = \"blue\"\n");
+    let themed = String::from_utf8(run(&["--color", "always"]).stdout).unwrap();
+    assert!(themed.contains("\x1b[34m1:6\x1b[0m"), "{:?}", themed);
+
+    dir.write("other.toml", "[theme]\npreset = \"sepia\"\n");
+    let invalid = run(&["--config", "other.toml"]);
+    assert_eq!(invalid.status.code(), Some(2));
+    let stderr = String::from_utf8(invalid.stderr).unwrap();
+    assert!(stderr.contains("unknown theme preset: sepia"), "{}", stderr);
+
+    dir.write("cargo-light.toml", "min_depth = 2\n");
+    let invalid = run(&[]);
+    assert_eq!(invalid.status.code(), Some(2));
+    let stderr = String::from_utf8(invalid.stderr).unwrap();
+    assert!(stderr.contains("unknown field `min_depth`"), "{}", stderr);
+}
+
+#[test]
+fn no_color_markers_mark_originals_and_shadows() {
+    let source = "
+fn f(x: u8) {
+    let x = x + 1;
+    drop(x);
+}
+";
+    let args = ["--color", "never", "--no-color-markers"];
+    let human = report("markers", source, &args);
+    assert!(
+        human.contains(" @ *2:6 (argument) →3:9 (expression)\n"),
+        "{}",
+        human
+    );
+}
+
+#[test]
+fn light_subcommand_is_optional() {
+    let dir = Scratch::new("subcommand");
+    dir.write(
+        "lib.rs",
+        "fn f(x: u8) {\n    let x = x + 1;\n    drop(x);\n}\n",
+    );
+    let args = ["-F", "lib.rs", "--format", "short"];
+    let direct = messages(dir.run(&args));
+    assert_eq!(
+        direct,
+        ["note[derived-shadow]: `x` shadows the binding at 1:6"]
+    );
+    assert_eq!(messages(dir.run(&[&["light"], &args[..]].concat())), direct);
+}
+
+#[test]
+fn packages_of_the_workspace_are_chosen_with_package_and_workspace() {
+    let dir = Scratch::new("packages");
+    dir.write("Cargo.toml", "[workspace]\nmembers = [\"a\", \"b\"]\n");
+    for package in ["a", "b"] {
+        dir.write(
+            &format!("{}/Cargo.toml", package),
+            &format!(
+                "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+                package
+            ),
+        );
+        dir.write(
+            &format!("{}/src/lib.rs", package),
+            "pub fn f(x: u8) {\n    let x = x + 1;\n    drop(x);\n}\n",
+        );
+    }
+    let files = |args: &[&str]| {
+        let output = dir.run(&[args, &["--format-template", "{file}"]].concat());
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    assert_eq!(files(&["--workspace"]), "a/src/lib.rs\nb/src/lib.rs\n");
+    assert_eq!(files(&["-p", "b"]), "b/src/lib.rs\n");
+    assert_eq!(files(&["--workspace", "--exclude", "a"]), "b/src/lib.rs\n");
+
+    let unknown = dir.run(&["-p", "c"]);
+    assert_eq!(unknown.status.code(), Some(2));
+    let stderr = String::from_utf8(unknown.stderr).unwrap();
+    assert!(
+        stderr.contains("package `c` is not in the workspace"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,107 @@
+//! What the integration tests share: a directory of their own to run
+//! `cargo-light` in, and the lines it reports.
+
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A directory of its own for a test, which is removed when it is dropped.
+pub struct Scratch(PathBuf);
+
+impl Scratch {
+    pub fn new(name: &str) -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let dir = env::temp_dir().join(format!(
+            "cargo-light-{}-{}-{}",
+            name,
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Scratch(dir)
+    }
+
+    /// Writes `source` to `file`, relative to the directory.
+    pub fn write(&self, file: &str, source: &str) {
+        let path = self.0.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, source).unwrap();
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// `cargo-light` with `args`, to run in the directory.
+    pub fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_cargo-light"));
+        command.args(args).current_dir(&self.0);
+        command
+    }
+
+    /// Runs `cargo-light` in the directory with `args`.
+    pub fn run(&self, args: &[&str]) -> Output {
+        self.command(args).output().unwrap()
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Writes `source` to a file of its own, named after the test, and runs
+/// `cargo-light` on it with `args`.
+pub fn light(name: &str, source: &str, args: &[&str]) -> Output {
+    let dir = Scratch::new(name);
+    dir.write("lib.rs", source);
+    dir.run(&[&["-F", "lib.rs"], args].concat())
+}
+
+/// The messages of the `file:line:col: message` lines of the shadows found
+/// in `source`.
+pub fn quickfix(name: &str, source: &str, args: &[&str]) -> Vec<String> {
+    messages(light(
+        name,
+        source,
+        &[&["--format", "quickfix"], args].concat(),
+    ))
+}
+
+pub fn messages(output: Output) -> Vec<String> {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            line.split_once(": ")
+                .map_or(line, |(_, message)| message)
+                .to_string()
+        })
+        .collect()
+}
+
+/// The `file:line:col: level[rule]: message` lines of `--format short`,
+/// without the file.
+pub fn short(name: &str, source: &str, args: &[&str]) -> Vec<String> {
+    messages(light(
+        name,
+        source,
+        &[&["--format", "short"], args].concat(),
+    ))
+}
+
+/// The stdout of `cargo-light` on `source` with `args`.
+pub fn report(name: &str, source: &str, args: &[&str]) -> String {
+    String::from_utf8(light(name, source, args).stdout).unwrap()
+}
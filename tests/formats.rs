@@ -0,0 +1,269 @@
+//! How the shadows found are written in each of the `--format`s.
+
+extern crate serde_json;
+
+mod common;
+
+use common::*;
+use serde_json::Value;
+
+/// A function with a single shadow, of `x` at 3:9.
+const SOURCE: &str = "
+fn f(x: u8) {
+    let x = x + 1;
+    drop(x);
+}
+";
+
+fn json(name: &str, args: &[&str]) -> Value {
+    serde_json::from_str(&report(name, SOURCE, args)).unwrap()
+}
+
+#[test]
+fn json_has_the_version_of_its_schema_and_every_binding() {
+    let report = json("json", &["--format", "json"]);
+    assert_eq!(report["version"], 2);
+    let file = &report["files"][0];
+    assert_eq!(file["file"], "lib.rs");
+    let function = &file["functions"][0];
+    assert_eq!(function["qualified_name"], "f");
+    assert_eq!(
+        (&function["line"], &function["end_line"]),
+        (&2.into(), &5.into())
+    );
+    let bindings = &function["variables"][0]["bindings"];
+    assert_eq!(bindings[0]["is_original"], true);
+    assert_eq!(bindings[0]["init"], "argument");
+    assert_eq!(
+        (&bindings[1]["line"], &bindings[1]["column"]),
+        (&3.into(), &9.into())
+    );
+    assert_eq!(bindings[1]["shadows"]["line"], 2);
+    assert_eq!(bindings[1]["class"], "derived");
+    assert_eq!(bindings[1]["snippet"], "let x = x + 1");
+}
+
+#[test]
+fn jsonl_has_a_line_per_shadow() {
+    let output = report("jsonl", SOURCE, &["--format", "jsonl"]);
+    let lines: Vec<Value> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 1, "{}", output);
+    assert_eq!(lines[0]["file"], "lib.rs");
+    assert_eq!(lines[0]["function"], "f");
+    assert_eq!(lines[0]["variable"], "x");
+    assert_eq!(
+        (&lines[0]["line"], &lines[0]["column"]),
+        (&3.into(), &9.into())
+    );
+    assert_eq!(lines[0]["shadows"]["line"], 2);
+    assert_eq!(lines[0]["class"], "derived");
+}
+
+#[test]
+fn sarif_has_the_rules_and_a_result_per_shadow() {
+    let log = json("sarif", &["--format", "sarif"]);
+    assert_eq!(log["version"], "2.1.0");
+    let run = &log["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "cargo-light");
+    assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 8);
+    let result = &run["results"][0];
+    assert_eq!(result["ruleId"], "derived-shadow");
+    assert_eq!(result["level"], "note");
+    let location = &result["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "lib.rs");
+    assert_eq!(location["region"]["startLine"], 3);
+    assert_eq!(location["region"]["startColumn"], 9);
+    assert_eq!(location["region"]["endColumn"], 10);
+    let related = &result["relatedLocations"][0]["physicalLocation"]["region"];
+    assert_eq!(
+        (&related["startLine"], &related["startColumn"]),
+        (&2.into(), &6.into())
+    );
+}
+
+#[test]
+fn fingerprints_stay_the_same_when_lines_move() {
+    let fingerprint = |name, source| {
+        let args = ["--format-template", "{fingerprint}"];
+        report(name, source, &args).trim().to_string()
+    };
+    let moved = format!("\n\n// A comment.\n{}", SOURCE);
+    assert_eq!(fingerprint("fingerprint", SOURCE), "2d322d1818b89df6");
+    assert_eq!(fingerprint("fingerprint-moved", &moved), "2d322d1818b89df6");
+}
+
+#[test]
+fn github_has_a_workflow_command_per_shadow() {
+    assert_eq!(
+        report("github", SOURCE, &["--format", "github"]),
+        "::notice file=lib.rs,line=3,col=9,endColumn=10,title=derived-shadow::`x` shadows the binding at 2:6\n"
+    );
+}
+
+#[test]
+fn gitlab_has_a_code_quality_issue_per_shadow() {
+    let issues = json("gitlab", &["--format", "gitlab"]);
+    assert_eq!(issues.as_array().unwrap().len(), 1);
+    assert_eq!(issues[0]["check_name"], "derived-shadow");
+    assert_eq!(issues[0]["description"], "`x` shadows the binding at 2:6");
+    assert_eq!(issues[0]["severity"], "info");
+    assert_eq!(issues[0]["location"]["path"], "lib.rs");
+    assert_eq!(issues[0]["location"]["lines"]["begin"], 3);
+    assert_eq!(issues[0]["fingerprint"].as_str().unwrap().len(), 16);
+}
+
+#[test]
+fn junit_has_a_failed_test_case_per_shadowed_variable() {
+    let output = report("junit", SOURCE, &["--format", "junit"]);
+    assert!(
+        output.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains("<testsuite name=\"lib.rs\" tests=\"1\" failures=\"1\">"),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains(
+            "<failure message=\"`x` shadows the binding at 2:6\" type=\"derived-shadow\">lib.rs:3:9: `x` shadows the binding at 2:6</failure>"
+        ),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn csv_and_tsv_have_a_row_per_shadow() {
+    let header = "file,module,function,variable,original_line,shadowed_line,shadow_line,shadow_column,distance,classification";
+    assert_eq!(
+        report("csv", SOURCE, &["--format", "csv"]),
+        format!("{}\nlib.rs,,f,x,2,2,3,9,1,derived\n", header)
+    );
+    assert_eq!(
+        report("tsv", SOURCE, &["--format", "tsv"]),
+        format!(
+            "{}\nlib.rs\t\tf\tx\t2\t2\t3\t9\t1\tderived\n",
+            header.replace(',', "\t")
+        )
+    );
+}
+
+#[test]
+fn short_has_a_compiler_style_line_per_shadow() {
+    assert_eq!(
+        report("short", SOURCE, &["--format", "short"]),
+        "lib.rs:3:9: note[derived-shadow]: `x` shadows the binding at 2:6\n"
+    );
+}
+
+#[test]
+fn show_source_underlines_the_bindings_of_each_shadow() {
+    let human = report(
+        "show-source",
+        SOURCE,
+        &["--color", "never", "--show-source"],
+    );
+    let excerpt = "
+     --> lib.rs:3:9
+      |
+    2 | fn f(x: u8) {
+      |      - first bound here
+    3 |     let x = x + 1;
+      |         ^ shadowed here
+";
+    assert!(human.contains(excerpt), "{}", human);
+    let plain = report("show-source-default", SOURCE, &["--color", "never"]);
+    assert!(!plain.contains("shadowed here"), "{}", plain);
+}
+
+#[test]
+fn oneline_has_a_plain_line_per_shadow() {
+    assert_eq!(
+        report("oneline", SOURCE, &["--format", "oneline"]),
+        "lib.rs:3:9: x in f: derived-shadow of 2:6\n"
+    );
+}
+
+#[test]
+fn templates_replace_each_placeholder_of_a_line() {
+    let template = "{{{var}}} {function} {rule} {level} {class} {orig_line}:{orig_col} {end_col} {distance}|{message}";
+    assert_eq!(
+        report("template", SOURCE, &["--format-template", template]),
+        "{x} f derived-shadow note derived 2:6 10 1|`x` shadows the binding at 2:6\n"
+    );
+    let output = light("template-unknown", SOURCE, &["--format-template", "{nope}"]);
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("unknown placeholder `{nope}`"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn badge_has_the_number_of_shadows() {
+    let badge = json("badge", &["--format", "badge"]);
+    assert_eq!(badge["schemaVersion"], 1);
+    assert_eq!(badge["label"], "shadows");
+    assert_eq!(badge["message"], "1");
+    let clean = report("badge-clean", "fn f() {}\n", &["--format", "badge"]);
+    let clean: Value = serde_json::from_str(&clean).unwrap();
+    assert_eq!(clean["message"], "0");
+    assert_ne!(clean["color"], badge["color"]);
+}
+
+#[test]
+fn teamcity_has_the_inspection_types_and_an_inspection_per_shadow() {
+    let output = report("teamcity", SOURCE, &["--format", "teamcity"]);
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 9, "{}", output);
+    assert!(lines[..8]
+        .iter()
+        .all(|line| line.starts_with("##teamcity[inspectionType ")));
+    assert!(
+        lines[1]
+            .contains("description='A local variable is shadowed by a value that doesn|'t use it"),
+        "{}",
+        lines[1]
+    );
+    assert_eq!(
+        lines[8],
+        "##teamcity[inspection typeId='derived-shadow' message='`x` shadows the binding at 2:6' file='lib.rs' line='3' SEVERITY='INFO']"
+    );
+}
+
+#[test]
+fn azure_has_a_logging_command_per_shadow() {
+    assert_eq!(
+        report("azure", SOURCE, &["--format", "azure"]),
+        "##vso[task.logissue type=warning;sourcepath=lib.rs;linenumber=3;columnnumber=9;code=derived-shadow]`x` shadows the binding at 2:6\n"
+    );
+}
+
+#[test]
+fn quickfix_has_an_uncolored_line_per_shadow_in_order() {
+    let source = "
+fn f(x: u8) {
+    let x = x + 1;
+    drop(x);
+}
+
+fn g(y: u8, x: u8) {
+    let y = y + 1;
+    let x = x + 1;
+    drop((x, y));
+}
+";
+    let expected = "lib.rs:3:9: `x` shadows the binding at 2:6
+lib.rs:8:9: `y` shadows the binding at 7:6
+lib.rs:9:9: `x` shadows the binding at 7:13
+";
+    let args = ["--format", "quickfix", "--color", "always"];
+    assert_eq!(report("quickfix", source, &args), expected);
+}
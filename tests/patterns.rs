@@ -0,0 +1,291 @@
+//! How the names bound by patterns, and the scopes they are visible in, are
+//! told apart.
+
+mod common;
+
+use common::short;
+
+#[test]
+fn tuple_patterns_bind_each_name() {
+    let source = "
+fn f(p: (u8, u8)) {
+    let (a, b) = p;
+    let (a, (b, c)) = (a, (b, 1));
+    drop((a, b, c));
+}
+";
+    assert_eq!(
+        short("tuple", source, &[]),
+        [
+            "note[derived-shadow]: `a` shadows the binding at 3:10",
+            "note[derived-shadow]: `b` shadows the binding at 3:13",
+        ]
+    );
+}
+
+#[test]
+fn struct_patterns_bind_their_fields_and_renamed_fields() {
+    let source = "
+struct Point { x: u8, y: u8 }
+
+fn f(p: Point, x: u8, w: u8) {
+    drop((x, w));
+    let Point { x, y: w, .. } = p;
+    drop((x, w));
+}
+";
+    assert_eq!(
+        short("struct", source, &[]),
+        [
+            "warning[unrelated-shadow]: `x` shadows the binding at 4:16",
+            "warning[unrelated-shadow]: `w` shadows the binding at 4:23",
+        ]
+    );
+}
+
+#[test]
+fn tuple_struct_patterns_bind_their_fields() {
+    let source = "
+struct Wrapper(u8);
+
+fn f(w: Wrapper, inner: u8) {
+    drop(inner);
+    let Wrapper(inner) = w;
+    drop(inner);
+}
+";
+    assert_eq!(
+        short("tuple-struct", source, &[]),
+        ["warning[unrelated-shadow]: `inner` shadows the binding at 4:18"]
+    );
+}
+
+#[test]
+fn slice_patterns_bind_their_elements_and_subslices() {
+    let source = "
+fn f(arr: [u8; 3], first: u8, rest: u8) {
+    drop((first, rest));
+    let [first, rest @ ..] = arr;
+    drop((first, rest));
+}
+";
+    assert_eq!(
+        short("slice", source, &[]),
+        [
+            "warning[unrelated-shadow]: `first` shadows the binding at 2:20",
+            "warning[unrelated-shadow]: `rest` shadows the binding at 2:31",
+        ]
+    );
+}
+
+#[test]
+fn nested_reference_and_parenthesized_patterns_are_walked() {
+    let source = "
+fn f(r: &(u8, (u8, Option<u8>)), a: u8, b: u8, c: u8) {
+    drop((a, b, c));
+    let &(a, ((b), Some(c))) = r else { return };
+    drop((a, b, c));
+}
+";
+    assert_eq!(
+        short("nested", source, &[]),
+        [
+            "warning[unrelated-shadow]: `a` shadows the binding at 2:34",
+            "warning[unrelated-shadow]: `b` shadows the binding at 2:41",
+            "warning[unrelated-shadow]: `c` shadows the binding at 2:48",
+        ]
+    );
+}
+
+#[test]
+fn or_patterns_bind_a_name_once() {
+    let source = "
+fn f(r: Result<u8, u8>, x: u8) {
+    drop(x);
+    let (Ok(x) | Err(x)) = r;
+    drop(x);
+}
+";
+    assert_eq!(
+        short("or", source, &[]),
+        ["warning[unrelated-shadow]: `x` shadows the binding at 2:25"]
+    );
+}
+
+#[test]
+fn locals_shadow_the_arguments_of_their_function() {
+    let source = "
+struct S;
+
+impl S {
+    fn trim(&self, input: &str) {
+        let input = input.trim();
+        drop(input);
+    }
+}
+
+fn f(input: &str) {
+    let input = input.trim();
+    drop(input);
+}
+";
+    assert_eq!(
+        short("arguments", source, &[]),
+        [
+            "note[derived-shadow]: `input` shadows the binding at 5:20",
+            "note[derived-shadow]: `input` shadows the binding at 11:6",
+        ]
+    );
+}
+
+#[test]
+fn closures_are_scopes_of_their_own() {
+    let source = "
+fn f(items: Vec<u8>) {
+    let x = 1;
+    let g = |x: u8| x + 1;
+    let h = || {
+        let y = 2;
+        drop(y);
+    };
+    let y = 3;
+    drop((x, g, h, y, items));
+}
+";
+    assert_eq!(
+        short("closures", source, &[]),
+        ["warning[unrelated-shadow]: `x` shadows the binding at 3:9 \
+             (closure argument shadows local)",]
+    );
+}
+
+#[test]
+fn match_arm_bindings_shadow_locals() {
+    let source = "
+fn f(x: Option<u8>, value: u8) {
+    drop(value);
+    match x {
+        Some(value) => drop(value),
+        None => {}
+    }
+}
+";
+    assert_eq!(
+        short("match-arms", source, &[]),
+        ["warning[unrelated-shadow]: `value` shadows the binding at 2:21"]
+    );
+}
+
+#[test]
+fn if_let_and_while_let_bindings_shadow_locals() {
+    let source = "
+fn f(conn: Option<u8>, mut iter: std::vec::IntoIter<u8>, item: u8) {
+    drop(item);
+    if let Some(conn) = conn {
+        drop(conn);
+    }
+    while let Some(item) = iter.next() {
+        drop(item);
+    }
+}
+";
+    assert_eq!(
+        short("if-let", source, &[]),
+        ["warning[unrelated-shadow]: `item` shadows the binding at 2:58"]
+    );
+    assert_eq!(
+        short("if-let-strict", source, &["--strict"]),
+        [
+            "note[derived-shadow]: `conn` shadows the binding at 2:6",
+            "warning[unrelated-shadow]: `item` shadows the binding at 2:58",
+        ]
+    );
+}
+
+#[test]
+fn for_loop_patterns_shadow_locals_and_are_shadowed_in_the_body() {
+    let source = "
+fn f(lines: Vec<String>, line: String) {
+    drop(line);
+    for line in lines {
+        let line = line.trim().to_string();
+        drop(line);
+    }
+}
+";
+    assert_eq!(
+        short("for", source, &[]),
+        [
+            "warning[unrelated-shadow]: `line` shadows the binding at 2:26",
+            "note[derived-shadow]: `line` shadows the binding at 4:9 (shadows loop variable)",
+        ]
+    );
+}
+
+#[test]
+fn modern_syntax_is_parsed() {
+    let source = "
+struct Buf<const N: usize>([u8; N]);
+
+async fn g() {}
+
+async fn f<const N: usize>(buf: Buf<N>, x: Option<u8>) {
+    let Some(y) = x else { return };
+    let y = y + 1;
+    async move { drop((buf, y)) }.await;
+    g().await;
+}
+";
+    assert_eq!(
+        short("modern", source, &[]),
+        ["note[derived-shadow]: `y` shadows the binding at 7:14"]
+    );
+}
+
+#[test]
+fn names_of_sibling_blocks_do_not_shadow_each_other() {
+    let source = "
+fn f(flag: bool) {
+    if flag {
+        let tmp = 1;
+        drop(tmp);
+    } else {
+        let tmp = 2;
+        drop(tmp);
+    }
+    {
+        let tmp = 3;
+        let tmp = tmp + 1;
+        drop(tmp);
+    }
+}
+";
+    assert_eq!(
+        short("scopes", source, &[]),
+        ["note[derived-shadow]: `tmp` shadows the binding at 11:13"]
+    );
+}
+
+#[test]
+fn nested_functions_have_locals_of_their_own() {
+    let source = "
+fn outer() {
+    let x = 1;
+    fn inner() {
+        let x = 2;
+        let x = x + 1;
+        drop(x);
+    }
+    let y = x;
+    let y = y + 1;
+    drop(y);
+}
+";
+    assert_eq!(
+        short("nested-fns", source, &[]),
+        [
+            "note[derived-shadow]: `y` shadows the binding at 9:9",
+            "note[derived-shadow]: `x` shadows the binding at 5:13",
+        ]
+    );
+}